@@ -0,0 +1,399 @@
+use crate::config::{tasks, Config};
+use crate::executor::{Executor, Outcome, Run};
+use crate::state::{self, Store};
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug)]
+pub enum Error {
+    UnknownTask(String),
+    /// The task runs after its `depends_on`, so there is no schedule to walk.
+    Unscheduled(String),
+    InvalidRange,
+    Store(state::Error),
+    /// Some runs failed, a rerun picks up from the first of them.
+    Failed(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownTask(name) => write!(f, "unknown task \"{name}\""),
+            Error::Unscheduled(name) => write!(
+                f,
+                "task \"{name}\" has no schedule, backfill its upstream tasks instead"
+            ),
+            Error::InvalidRange => write!(f, "`--from` must be before `--to`"),
+            Error::Store(err) => write!(f, "{err}"),
+            Error::Failed(failed) => write!(f, "{failed} backfill runs failed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<state::Error> for Error {
+    fn from(err: state::Error) -> Self {
+        Error::Store(err)
+    }
+}
+
+/// Replays a task over a past range.
+///
+/// Every occurrence of the task's schedule in `(from, to]` runs with the
+/// previous occurrence as its last run, the first one with `from`. Runs see
+/// the task's state defaults and leave the stored state alone. Progress
+/// is kept in the store as the latest run up to which everything succeeded,
+/// so an interrupted or failed backfill resumes from there.
+pub struct Backfill {
+    task: Arc<tasks::Task>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    parallelism: NonZeroUsize,
+    executor: Arc<dyn Executor>,
+    store: Arc<dyn Store>,
+}
+
+impl Backfill {
+    pub fn new(
+        config: Config,
+        task: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        parallelism: NonZeroUsize,
+        executor: Arc<dyn Executor>,
+        store: Arc<dyn Store>,
+    ) -> Result<Self, Error> {
+        if from >= to {
+            return Err(Error::InvalidRange);
+        }
+
+        let task = config
+            .into_tasks()
+            .into_iter()
+            .find(|candidate| candidate.name() == task)
+            .ok_or_else(|| Error::UnknownTask(String::from(task)))?;
+
+        if task
+            .options()
+            .schedule
+            .is_none()
+        {
+            return Err(Error::Unscheduled(String::from(task.name())));
+        }
+
+        Ok(Self {
+            task: Arc::new(task),
+            from,
+            to,
+            parallelism,
+            executor,
+            store,
+        })
+    }
+
+    fn id(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            self.task.name(),
+            self.from.to_rfc3339(),
+            self.to.to_rfc3339()
+        )
+    }
+
+    /// Runs the backfill until it is done or `shutdown` is cancelled, then
+    /// waits for the runs in flight.
+    pub async fn run(self, shutdown: CancellationToken) -> Result<(), Error> {
+        let id = self.id();
+        let name = self.task.name();
+
+        let progress_id = id.clone();
+        let progress = state::blocking(&self.store, move |store| {
+            store.backfill_progress(&progress_id)
+        })
+        .await?;
+
+        let mut last = match progress {
+            Some(done_until) => {
+                tracing::info!(task = name, %done_until, "resuming backfill");
+                done_until
+            }
+            None => self.from,
+        };
+
+        let mut runs = JoinSet::new();
+        // Runs not yet covered by the progress, oldest first, with whether
+        // they succeeded.
+        let mut pending: VecDeque<(DateTime<Utc>, bool)> = VecDeque::new();
+        let mut exhausted = false;
+        let mut failed = 0;
+
+        loop {
+            while runs.len() < self.parallelism.get() && !exhausted && !shutdown.is_cancelled() {
+                let Some(scheduled_at) = self
+                    .task
+                    .next_run(last, self.from)
+                    .filter(|next| *next <= self.to)
+                else {
+                    exhausted = true;
+                    break;
+                };
+
+                let run = Run {
+                    scheduled_at,
+                    last_scheduled_at: Some(last),
+                    state: self.task.state_defaults(),
+                };
+                let task = self.task.clone();
+                let executor = self.executor.clone();
+
+                runs.spawn(async move {
+                    let report = executor
+                        .execute(&task, &run)
+                        .await;
+
+                    (run.scheduled_at, report.outcome)
+                });
+
+                pending.push_back((scheduled_at, false));
+                last = scheduled_at;
+            }
+
+            let Some(result) = runs.join_next().await else {
+                break;
+            };
+            let (scheduled_at, outcome) = result.expect("backfill run panicked");
+
+            match outcome {
+                Outcome::Success => {
+                    tracing::info!(task = name, %scheduled_at, "backfill run succeeded");
+
+                    if let Some(entry) = pending
+                        .iter_mut()
+                        .find(|(pending_at, _)| *pending_at == scheduled_at)
+                    {
+                        entry.1 = true;
+                    }
+                }
+                outcome => {
+                    tracing::warn!(task = name, %scheduled_at, ?outcome, "backfill run failed");
+                    failed += 1;
+                }
+            }
+
+            let mut done_until = None;
+
+            while let Some((scheduled_at, true)) = pending.front() {
+                done_until = Some(*scheduled_at);
+                pending.pop_front();
+            }
+
+            if let Some(done_until) = done_until {
+                let id = id.clone();
+
+                state::blocking(&self.store, move |store| {
+                    store.record_backfill_progress(&id, done_until)
+                })
+                .await?;
+            }
+        }
+
+        if failed > 0 {
+            return Err(Error::Failed(failed));
+        }
+
+        if shutdown.is_cancelled() && !exhausted {
+            tracing::info!(task = name, "backfill interrupted, rerun to resume");
+        } else {
+            tracing::info!(task = name, "backfill finished");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::Report;
+    use crate::state::json::JsonStore;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    /// Records runs and fails the ones scheduled at `fail_at`.
+    #[derive(Default)]
+    struct Recorder {
+        runs: Mutex<Vec<Run>>,
+        fail_at: Option<DateTime<Utc>>,
+    }
+
+    #[async_trait]
+    impl Executor for Recorder {
+        async fn execute(&self, _task: &tasks::Task, run: &Run) -> Report {
+            self.runs
+                .lock()
+                .unwrap()
+                .push(run.clone());
+
+            let outcome = if Some(run.scheduled_at) == self.fail_at {
+                Outcome::Failure(String::from("unexpected status 500"))
+            } else {
+                Outcome::Success
+            };
+
+            Report {
+                outcome,
+                attempts: vec![],
+                pages: vec![],
+                state: state::Values::new(),
+            }
+        }
+    }
+
+    fn config() -> Config {
+        "
+          tasks:
+            - type: http
+              name: load_data
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"0 * * * *\""
+            .parse()
+            .unwrap()
+    }
+
+    fn hour(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, hour, 0, 0)
+            .unwrap()
+    }
+
+    fn backfill(recorder: Arc<Recorder>, store: Arc<JsonStore>, parallelism: usize) -> Backfill {
+        Backfill::new(
+            config(),
+            "load_data",
+            hour(0),
+            hour(4),
+            NonZeroUsize::new(parallelism).unwrap(),
+            recorder,
+            store,
+        )
+        .unwrap()
+    }
+
+    fn window(from: u32, to: u32) -> Run {
+        Run {
+            scheduled_at: hour(to),
+            last_scheduled_at: Some(hour(from)),
+            state: state::Values::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backfill_walks_schedule_over_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(JsonStore::open(dir.path().join("state.json")).unwrap());
+        let recorder = Arc::new(Recorder::default());
+
+        backfill(recorder.clone(), store.clone(), 3)
+            .run(CancellationToken::new())
+            .await
+            .unwrap();
+
+        let mut runs = recorder
+            .runs
+            .lock()
+            .unwrap()
+            .clone();
+        runs.sort_by_key(|run| run.scheduled_at);
+
+        assert_eq!(
+            vec![window(0, 1), window(1, 2), window(2, 3), window(3, 4)],
+            runs
+        );
+        assert_eq!(
+            Some(hour(4)),
+            store
+                .backfill_progress("load_data/2026-01-01T00:00:00+00:00/2026-01-01T04:00:00+00:00")
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backfill_resumes_from_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(JsonStore::open(dir.path().join("state.json")).unwrap());
+        let recorder = Arc::new(Recorder::default());
+        let backfill = backfill(recorder.clone(), store.clone(), 1);
+
+        store
+            .record_backfill_progress(&backfill.id(), hour(2))
+            .unwrap();
+        backfill
+            .run(CancellationToken::new())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec![window(2, 3), window(3, 4)],
+            *recorder.runs.lock().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backfill_keeps_progress_before_failed_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(JsonStore::open(dir.path().join("state.json")).unwrap());
+        let recorder = Arc::new(Recorder {
+            fail_at: Some(hour(2)),
+            ..Recorder::default()
+        });
+        let backfill = backfill(recorder.clone(), store.clone(), 1);
+        let id = backfill.id();
+
+        let err = backfill
+            .run(CancellationToken::new())
+            .await
+            .err();
+
+        assert!(matches!(err, Some(Error::Failed(1))));
+        assert_eq!(
+            4,
+            recorder
+                .runs
+                .lock()
+                .unwrap()
+                .len()
+        );
+        assert_eq!(
+            Some(hour(1)),
+            store
+                .backfill_progress(&id)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_backfill_rejects_unknown_task() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(JsonStore::open(dir.path().join("state.json")).unwrap());
+
+        let err = Backfill::new(
+            config(),
+            "push_data",
+            hour(0),
+            hour(4),
+            NonZeroUsize::MIN,
+            Arc::new(Recorder::default()),
+            store,
+        )
+        .err();
+
+        assert!(matches!(err, Some(Error::UnknownTask(name)) if name == "push_data"));
+    }
+}
@@ -0,0 +1,281 @@
+use super::duration;
+use super::extract::Path;
+use regex::Regex;
+use serde::de::{Error, Visitor};
+use serde::Deserialize;
+use std::fmt;
+use std::time::Duration;
+
+/// A check a successful response must also pass, e.g. that `$.status` is
+/// `ok`. A JSON value or header without a check only has to exist.
+#[derive(Debug, PartialEq)]
+pub enum Assertion {
+    Json { path: Path, check: Check },
+    Header { name: String, check: Check },
+    BodyContains(String),
+    MaxLatency(Duration),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Check {
+    Equals(serde_json::Value),
+    Matches(Pattern),
+    Exists(bool),
+    Compare(Comparison, f64),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Comparison {
+    GreaterThan,
+    AtLeast,
+    LessThan,
+    AtMost,
+}
+
+impl Comparison {
+    pub fn holds(&self, value: f64, bound: f64) -> bool {
+        match self {
+            Comparison::GreaterThan => value > bound,
+            Comparison::AtLeast => value >= bound,
+            Comparison::LessThan => value < bound,
+            Comparison::AtMost => value <= bound,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Comparison::GreaterThan => write!(f, "greater than"),
+            Comparison::AtLeast => write!(f, "at least"),
+            Comparison::LessThan => write!(f, "less than"),
+            Comparison::AtMost => write!(f, "at most"),
+        }
+    }
+}
+
+/// A regex, compared by its source.
+#[derive(Debug)]
+pub struct Pattern(pub Regex);
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+const SUBJECTS: &[&str] = &["json", "header", "body_contains", "max_latency"];
+const CHECKS: &[&str] = &[
+    "equals",
+    "matches",
+    "exists",
+    "greater_than",
+    "at_least",
+    "less_than",
+    "at_most",
+];
+
+struct AssertionVisitor;
+
+impl<'de> Visitor<'de> for AssertionVisitor {
+    type Value = Assertion;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "one of `json`, `header`, `body_contains` or `max_latency`, with a check such as `equals`"
+        )
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let mut subject = None;
+        let mut check = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            let value = match key.as_str() {
+                "json" => Some(Assertion::Json {
+                    path: map.next_value()?,
+                    check: Check::Exists(true),
+                }),
+                "header" => Some(Assertion::Header {
+                    name: map.next_value()?,
+                    check: Check::Exists(true),
+                }),
+                "body_contains" => Some(Assertion::BodyContains(map.next_value()?)),
+                "max_latency" => {
+                    let latency: String = map.next_value()?;
+
+                    Some(Assertion::MaxLatency(
+                        duration::parse(&latency).map_err(Error::custom)?,
+                    ))
+                }
+                _ => None,
+            };
+
+            if let Some(value) = value {
+                if subject
+                    .replace(value)
+                    .is_some()
+                {
+                    return Err(Error::custom(format!(
+                        "an assertion checks only one of {}",
+                        list(SUBJECTS)
+                    )));
+                }
+
+                continue;
+            }
+
+            let value = match key.as_str() {
+                "equals" => Check::Equals(map.next_value()?),
+                "matches" => {
+                    let pattern: String = map.next_value()?;
+
+                    Check::Matches(Pattern(Regex::new(&pattern).map_err(|err| {
+                        Error::custom(format!("invalid regex \"{pattern}\": {err}"))
+                    })?))
+                }
+                "exists" => Check::Exists(map.next_value()?),
+                "greater_than" => Check::Compare(Comparison::GreaterThan, map.next_value()?),
+                "at_least" => Check::Compare(Comparison::AtLeast, map.next_value()?),
+                "less_than" => Check::Compare(Comparison::LessThan, map.next_value()?),
+                "at_most" => Check::Compare(Comparison::AtMost, map.next_value()?),
+                key => {
+                    let fields: Vec<&str> = SUBJECTS
+                        .iter()
+                        .chain(CHECKS)
+                        .copied()
+                        .collect();
+
+                    return Err(Error::custom(format!(
+                        "unknown field `{key}`, expected one of {}",
+                        list(&fields)
+                    )));
+                }
+            };
+
+            if check.replace(value).is_some() {
+                return Err(Error::custom(format!(
+                    "an assertion has only one of {}",
+                    list(CHECKS)
+                )));
+            }
+        }
+
+        match (subject, check) {
+            (None, _) => Err(Error::custom(format!(
+                "an assertion needs one of {}",
+                list(SUBJECTS)
+            ))),
+            (Some(Assertion::Json { path, .. }), Some(check)) => {
+                Ok(Assertion::Json { path, check })
+            }
+            (Some(Assertion::Header { name, .. }), Some(check)) => {
+                Ok(Assertion::Header { name, check })
+            }
+            (Some(_), Some(_)) => Err(Error::custom(
+                "`body_contains` and `max_latency` take no further check",
+            )),
+            (Some(subject), None) => Ok(subject),
+        }
+    }
+}
+
+fn list(fields: &[&str]) -> String {
+    fields
+        .iter()
+        .map(|field| format!("`{field}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl<'de> Deserialize<'de> for Assertion {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(AssertionVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn assertion(input: &str) -> Assertion {
+        serde_yml::from_str(input).unwrap()
+    }
+
+    fn failure(input: &str, expected: &str) {
+        let err = serde_yml::from_str::<Assertion>(input)
+            .err()
+            .unwrap()
+            .to_string();
+
+        assert!(err.contains(expected), "{err}");
+    }
+
+    #[test]
+    fn test_deserialize_assertion() {
+        assert_eq!(
+            Assertion::Json {
+                path: "$.status".parse().unwrap(),
+                check: Check::Equals(json!("ok")),
+            },
+            assertion("{json: $.status, equals: ok}")
+        );
+        assert_eq!(
+            Assertion::Json {
+                path: "/items".parse().unwrap(),
+                check: Check::Exists(true),
+            },
+            assertion("json: /items")
+        );
+        assert_eq!(
+            Assertion::Json {
+                path: "$.count".parse().unwrap(),
+                check: Check::Compare(Comparison::AtLeast, 1.0),
+            },
+            assertion("{json: $.count, at_least: 1}")
+        );
+        assert_eq!(
+            Assertion::Header {
+                name: String::from("Content-Type"),
+                check: Check::Matches(Pattern(Regex::new("^application/json").unwrap())),
+            },
+            assertion("{header: Content-Type, matches: ^application/json}")
+        );
+        assert_eq!(
+            Assertion::BodyContains(String::from("done")),
+            assertion("body_contains: done")
+        );
+        assert_eq!(
+            Assertion::MaxLatency(Duration::from_millis(1500)),
+            assertion("max_latency: 1500ms")
+        );
+    }
+
+    #[test]
+    fn test_deserialize_assertion_errors() {
+        failure("equals: ok", "an assertion needs one of `json`");
+        failure(
+            "{json: $.status, header: X-Status}",
+            "an assertion checks only one of",
+        );
+        failure(
+            "{json: $.status, equals: ok, exists: true}",
+            "an assertion has only one of",
+        );
+        failure("{json: $.status, is: ok}", "unknown field `is`");
+        failure("{json: $.id, matches: \"[\"}", "invalid regex \"[\"");
+        failure(
+            "{body_contains: done, equals: ok}",
+            "`body_contains` and `max_latency` take no further check",
+        );
+        failure("max_latency: soon", "invalid duration \"soon\"");
+    }
+}
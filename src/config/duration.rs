@@ -0,0 +1,34 @@
+use serde::de::{Error, Visitor};
+use std::fmt;
+use std::time::Duration;
+
+/// Parses a human readable duration such as `90s`, `1h 30m` or `250ms`.
+pub fn parse(value: &str) -> Result<Duration, String> {
+    humantime::parse_duration(value)
+        .map_err(|err| format!("invalid duration \"{value}\", expected e.g. 90s or 1h 30m: {err}"))
+}
+
+/// For `#[serde(deserialize_with = "duration::deserialize")]` fields.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserializer.deserialize_str(DurationVisitor)
+}
+
+struct DurationVisitor;
+
+impl<'de> Visitor<'de> for DurationVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "duration, e.g. 90s or 1h 30m")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        parse(value).map_err(Error::custom)
+    }
+}
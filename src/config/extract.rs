@@ -0,0 +1,211 @@
+use serde::de::{Error, Visitor};
+use serde::Deserialize;
+use serde_json_path::JsonPath;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Where a value is read from a JSON response: a JSONPath such as
+/// `$.items[-1:].id` or a JSON pointer such as `/next_page_token`.
+#[derive(Debug, PartialEq)]
+pub enum Path {
+    JsonPath(JsonPath),
+    Pointer(String),
+}
+
+impl Path {
+    /// Returns the value at the path. A JSONPath that matches several nodes
+    /// yields them as an array.
+    pub fn find(&self, value: &serde_json::Value) -> Option<serde_json::Value> {
+        match self {
+            Path::JsonPath(path) => {
+                let nodes = path.query(value);
+
+                match nodes.len() {
+                    0 => None,
+                    1 => nodes.first().cloned(),
+                    _ => Some(serde_json::Value::Array(
+                        nodes
+                            .all()
+                            .into_iter()
+                            .cloned()
+                            .collect(),
+                    )),
+                }
+            }
+            Path::Pointer(pointer) => value
+                .pointer(pointer)
+                .cloned(),
+        }
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Path::JsonPath(path) => write!(f, "{path}"),
+            Path::Pointer(pointer) => write!(f, "{pointer}"),
+        }
+    }
+}
+
+impl FromStr for Path {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with('$') {
+            return JsonPath::parse(s)
+                .map(Path::JsonPath)
+                .map_err(|err| format!("invalid JSONPath \"{s}\": {err}"));
+        }
+
+        if s.is_empty() || s.starts_with('/') {
+            return Ok(Path::Pointer(String::from(s)));
+        }
+
+        Err(format!(
+            "invalid path \"{s}\", expected a JSONPath such as $.cursor or a JSON pointer such as /cursor"
+        ))
+    }
+}
+
+struct PathVisitor;
+
+impl<'de> Visitor<'de> for PathVisitor {
+    type Value = Path;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "JSONPath or JSON pointer")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        value
+            .parse()
+            .map_err(Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Path {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(PathVisitor)
+    }
+}
+
+/// Extracted values by name, as a task keeps them between runs.
+pub type Values = BTreeMap<String, serde_json::Value>;
+
+/// A value a task takes from its response and keeps for its next runs,
+/// declared either as a bare path or as `{path, default}`.
+#[derive(Debug, PartialEq)]
+pub struct Extract {
+    pub path: Path,
+    /// Used until a value has been extracted.
+    pub default: Option<serde_json::Value>,
+}
+
+struct ExtractVisitor;
+
+impl<'de> Visitor<'de> for ExtractVisitor {
+    type Value = Extract;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "path or `path` and `default`")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Ok(Extract {
+            path: PathVisitor.visit_str(value)?,
+            default: None,
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let mut path = None;
+        let mut default = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "path" => path = Some(map.next_value()?),
+                "default" => default = Some(map.next_value()?),
+                key => return Err(Error::unknown_field(key, &["path", "default"])),
+            }
+        }
+
+        Ok(Extract {
+            path: path.ok_or_else(|| Error::missing_field("path"))?,
+            default,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Extract {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ExtractVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_deserialize_extract() {
+        let extract: Extract = serde_yml::from_str("$.next_page_token").unwrap();
+        assert_eq!(None, extract.default);
+        assert!(matches!(extract.path, Path::JsonPath(_)));
+
+        let extract: Extract = serde_yml::from_str(
+            "
+            path: /max_id
+            default: 0",
+        )
+        .unwrap();
+        assert_eq!(Path::Pointer(String::from("/max_id")), extract.path);
+        assert_eq!(Some(json!(0)), extract.default);
+
+        let err = serde_yml::from_str::<Extract>("max_id")
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .contains("invalid path \"max_id\""));
+        assert!(serde_yml::from_str::<Extract>("$.[").is_err());
+        assert!(serde_yml::from_str::<Extract>("default: 0").is_err());
+    }
+
+    #[test]
+    fn test_path_find() {
+        let response = json!({
+            "cursor": {"next": "abc"},
+            "items": [{"id": 1}, {"id": 2}],
+        });
+        let find = |path: &str| {
+            path.parse::<Path>()
+                .unwrap()
+                .find(&response)
+        };
+
+        assert_eq!(Some(json!("abc")), find("/cursor/next"));
+        assert_eq!(Some(json!("abc")), find("$.cursor.next"));
+        assert_eq!(Some(json!(2)), find("$.items[-1].id"));
+        assert_eq!(Some(json!([1, 2])), find("$.items[*].id"));
+        assert_eq!(None, find("$.missing"));
+        assert_eq!(None, find("/missing"));
+    }
+}
@@ -1,10 +1,13 @@
-use crate::config::value;
+use crate::config::{assertion, extract, pagination, tasks, value};
+use indexmap::IndexMap;
 use reqwest::Url;
 use serde::de::{Error, Visitor};
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 use serde_yml::Mapping;
 use std::collections::HashMap;
 use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
 
 #[derive(Debug, PartialEq)]
 pub enum Method {
@@ -13,15 +16,29 @@ pub enum Method {
     Delete,
     Put,
     Patch,
+    Head,
+    Options,
+    Trace,
+    Connect,
+    /// An extension method such as WebDAV's `PROPFIND`.
+    Custom(String),
 }
 
+const METHODS: &[&str] = &[
+    "GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS", "TRACE", "CONNECT",
+];
+
 struct MethodVisitor;
 
 impl<'de> Visitor<'de> for MethodVisitor {
     type Value = Method;
 
     fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "expected one of [GET, POST, PUT, DELETE, PATCH]")
+        write!(
+            f,
+            "expected one of [{}] or an uppercase extension method such as PROPFIND",
+            METHODS.join(", ")
+        )
     }
 
     fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
@@ -36,10 +53,24 @@ impl<'de> Visitor<'de> for MethodVisitor {
             "PUT" => Ok(Put),
             "DELETE" => Ok(Delete),
             "PATCH" => Ok(Patch),
-            value => Err(Error::unknown_variant(
-                value,
-                &["GET", "POST", "PUT", "DELETE", "PATCH"],
-            )),
+            "HEAD" => Ok(Head),
+            "OPTIONS" => Ok(Options),
+            "TRACE" => Ok(Trace),
+            "CONNECT" => Ok(Connect),
+            // Uppercase only, so that e.g. `get` is not taken for an
+            // extension method.
+            value
+                if !value.is_empty()
+                    && value
+                        .bytes()
+                        .all(|byte| byte.is_ascii_uppercase() || byte == b'-' || byte == b'_') =>
+            {
+                Ok(Custom(String::from(value)))
+            }
+            value => Err(Error::custom(format!(
+                "invalid method \"{value}\", expected one of [{}] or an uppercase extension method such as PROPFIND",
+                METHODS.join(", ")
+            ))),
         }
     }
 }
@@ -53,24 +84,350 @@ impl<'de> Deserialize<'de> for Method {
     }
 }
 
+/// An entry of `success_status_codes`: a code such as `200`, a class such
+/// as `2xx`, a range such as `200-299`, or any of them negated as `!204`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct StatusCode {
+    pub codes: RangeInclusive<u16>,
+    pub negated: bool,
+}
+
+impl StatusCode {
+    pub fn contains(&self, status: u16) -> bool {
+        self.codes.contains(&status)
+    }
+}
+
+impl From<u16> for StatusCode {
+    fn from(code: u16) -> Self {
+        Self {
+            codes: code..=code,
+            negated: false,
+        }
+    }
+}
+
+struct StatusCodeVisitor;
+
+impl StatusCodeVisitor {
+    const EXPECTED: &'static str = "expected e.g. 200, 2xx, 200-299 or !204";
+
+    fn code<E>(value: &str) -> Result<u16, E>
+    where
+        E: serde::de::Error,
+    {
+        let code: u16 = value.parse().map_err(|_| {
+            Error::custom(format!(
+                "invalid status code \"{value}\", {}",
+                Self::EXPECTED
+            ))
+        })?;
+
+        Self::check(code as u64)
+    }
+
+    fn check<E>(code: u64) -> Result<u16, E>
+    where
+        E: serde::de::Error,
+    {
+        match code {
+            100..=599 => Ok(code as u16),
+            code => Err(Error::custom(format!(
+                "status code {code} is not between 100 and 599"
+            ))),
+        }
+    }
+}
+
+impl<'de> Visitor<'de> for StatusCodeVisitor {
+    type Value = StatusCode;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "status code, {}", Self::EXPECTED)
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Self::check(value).map(StatusCode::from)
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match u64::try_from(value) {
+            Ok(value) => self.visit_u64(value),
+            Err(_) => Err(Error::custom(format!(
+                "status code {value} is not between 100 and 599"
+            ))),
+        }
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        let (negated, codes) = match value.strip_prefix('!') {
+            Some(codes) => (true, codes.trim()),
+            None => (false, value.trim()),
+        };
+
+        let codes = match (codes.split_once('-'), codes.strip_suffix("xx")) {
+            (Some((start, end)), _) => {
+                let (start, end) = (Self::code(start.trim())?, Self::code(end.trim())?);
+
+                if start > end {
+                    return Err(Error::custom(format!(
+                        "status code range \"{value}\" is empty"
+                    )));
+                }
+
+                start..=end
+            }
+            (None, Some(class)) => match class {
+                "1" | "2" | "3" | "4" | "5" => {
+                    let start = Self::code(&format!("{class}00"))?;
+
+                    start..=start + 99
+                }
+                _ => {
+                    return Err(Error::custom(format!(
+                        "invalid status class \"{value}\", expected one of 1xx to 5xx"
+                    )))
+                }
+            },
+            (None, None) => {
+                let code = Self::code(codes)?;
+
+                code..=code
+            }
+        };
+
+        Ok(StatusCode { codes, negated })
+    }
+}
+
+impl<'de> Deserialize<'de> for StatusCode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(StatusCodeVisitor)
+    }
+}
+
 #[derive(Deserialize, Debug, PartialEq)]
 pub struct Task {
     name: String,
+    #[serde(flatten)]
+    request: Request,
+    /// Values kept from the response for the next runs, read as
+    /// `state.<name>` sources.
+    #[serde(default)]
+    extract: HashMap<String, extract::Extract>,
+    pagination: Option<pagination::Pagination>,
+    /// Checks a response must pass, beyond its status, to count as a success.
+    #[serde(default, rename = "assert")]
+    assertions: Vec<assertion::Assertion>,
+    #[serde(flatten)]
+    options: tasks::Options,
+}
+
+impl Task {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn options(&self) -> &tasks::Options {
+        &self.options
+    }
+
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    pub fn extract(&self) -> &HashMap<String, extract::Extract> {
+        &self.extract
+    }
+
+    pub fn pagination(&self) -> Option<&pagination::Pagination> {
+        self.pagination.as_ref()
+    }
+
+    pub fn assertions(&self) -> &[assertion::Assertion] {
+        &self.assertions
+    }
+}
+
+/// What to send, shared by http tasks and pipeline steps.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(remote = "Self")]
+pub struct Request {
     method: Method,
-    url: Url,
+    url: Endpoint,
     #[serde(default)]
     headers: Headers,
     #[serde(default)]
-    success_status_codes: Vec<u16>,
+    query: Query,
+    #[serde(default)]
+    success_status_codes: Vec<StatusCode>,
     body: Option<Body>,
 }
 
+impl<'de> Deserialize<'de> for Request {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let request = Request::deserialize(deserializer)?;
+
+        let sets_content_type = request
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-type"));
+
+        if sets_content_type && matches!(request.body, Some(Body::Multipart(_))) {
+            return Err(Error::custom(
+                "a multipart body sets its own Content-Type with the boundary, leave it out of `headers`",
+            ));
+        }
+
+        Ok(request)
+    }
+}
+
+impl Request {
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    pub fn url(&self) -> &Endpoint {
+        &self.url
+    }
+
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+
+    pub fn body(&self) -> Option<&Body> {
+        self.body.as_ref()
+    }
+
+    /// A status is a success if no negated entry of `success_status_codes`
+    /// matches it and any other entry does. Without other entries only 200
+    /// is a success.
+    pub fn is_success_status(&self, status: u16) -> bool {
+        let (negated, accepted): (Vec<&StatusCode>, Vec<&StatusCode>) = self
+            .success_status_codes
+            .iter()
+            .partition(|code| code.negated);
+
+        if negated
+            .iter()
+            .any(|code| code.contains(status))
+        {
+            return false;
+        }
+
+        if accepted.is_empty() {
+            return status == 200;
+        }
+
+        accepted
+            .iter()
+            .any(|code| code.contains(status))
+    }
+
+    /// Every value rendered into the request.
+    pub fn values(&self) -> Vec<&value::Value> {
+        let mut values: Vec<&value::Value> = self
+            .headers
+            .iter()
+            .chain(self.query.iter())
+            .map(|(_, value)| value)
+            .collect();
+
+        if let Endpoint::Value(value) = &self.url {
+            values.push(value);
+        }
+
+        match &self.body {
+            Some(
+                Body::Json(value)
+                | Body::Form(value)
+                | Body::Text(Text { value, .. })
+                | Body::Xml(value)
+                | Body::MsgPack(value)
+                | Body::Cbor(value),
+            ) => values.push(value),
+            Some(Body::Multipart(multipart)) => values.extend(&multipart.fields),
+            Some(Body::File(_)) | None => {}
+        }
+
+        values
+    }
+}
+
+/// Where a request goes: a fixed URL checked at load, or a value rendered
+/// into one at send time.
 #[derive(Debug, PartialEq)]
-pub struct Headers(HashMap<String, value::Value>);
+pub enum Endpoint {
+    Fixed(Url),
+    Value(value::Value),
+}
 
-impl Default for Headers {
-    fn default() -> Self {
-        Self(HashMap::default())
+struct EndpointVisitor;
+
+impl<'de> Visitor<'de> for EndpointVisitor {
+    type Value = Endpoint;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "url or yaml entry")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Url::parse(value)
+            .map(Endpoint::Fixed)
+            .map_err(|err| Error::custom(format!("invalid url \"{value}\": {err}")))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let entry = Mapping::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+
+        value::Value::from_basic_entry(&serde_yml::Value::Mapping(entry))
+            .map(Endpoint::Value)
+            .map_err(|err| err.to_de_error())
+    }
+}
+
+impl<'de> Deserialize<'de> for Endpoint {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(EndpointVisitor)
+    }
+}
+
+/// Headers in the order they are written.
+#[derive(Debug, PartialEq, Default)]
+pub struct Headers(IndexMap<String, value::Value>);
+
+impl Headers {
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &value::Value)> {
+        self.0.iter()
     }
 }
 
@@ -87,10 +444,10 @@ impl<'de> Visitor<'de> for HeadersVisitor {
     where
         A: serde::de::MapAccess<'de>,
     {
-        let mut headers: HashMap<String, value::Value> = HashMap::new();
+        let mut headers: IndexMap<String, value::Value> = IndexMap::new();
 
         loop {
-            let entry: Option<(String, Mapping)> = map.next_entry()?;
+            let entry: Option<(String, serde_yml::Value)> = map.next_entry()?;
 
             if let Some((key, entry)) = entry {
                 let value = value::Value::from_basic_entry(&entry);
@@ -119,9 +476,190 @@ impl<'de> Deserialize<'de> for Headers {
     }
 }
 
+/// Query parameters added to the url at send time, in the order they are
+/// written. An array sends its items as repeated parameters.
+#[derive(Debug, PartialEq, Default)]
+pub struct Query(IndexMap<String, value::Value>);
+
+impl Query {
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &value::Value)> {
+        self.0.iter()
+    }
+}
+
+struct QueryVisitor;
+
+impl<'de> Visitor<'de> for QueryVisitor {
+    type Value = Query;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "query parameter: yaml entry")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let mut query: IndexMap<String, value::Value> = IndexMap::new();
+
+        while let Some((key, entry)) = map.next_entry::<String, serde_yml::Value>()? {
+            let value = value::Value::from_entry(&entry).map_err(|err| err.to_de_error())?;
+
+            if !is_flat(&value) {
+                return Err(Error::custom(format!(
+                    "query parameter `{key}` must be a scalar or an array of scalars"
+                )));
+            }
+
+            query.insert(key, value);
+        }
+
+        Ok(Query(query))
+    }
+}
+
+impl<'de> Deserialize<'de> for Query {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(QueryVisitor)
+    }
+}
+
+/// Whether `value` is a scalar or an array of scalars, as query parameters
+/// and form fields have to be.
+fn is_flat(value: &value::Value) -> bool {
+    let is_scalar =
+        |value: &value::Value| !matches!(value, value::Value::Array(_) | value::Value::Object(_));
+
+    match value {
+        value::Value::Array(items) => items.iter().all(is_scalar),
+        value => is_scalar(value),
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Body {
     Json(value::Value),
+    /// An object sent as `application/x-www-form-urlencoded`.
+    Form(value::Value),
+    Multipart(Multipart),
+    Text(Text),
+    /// A local file streamed as the body.
+    File(File),
+    /// An object with a single root element, see `executor::xml` for how
+    /// values map to elements and attributes.
+    Xml(value::Value),
+    MsgPack(value::Value),
+    Cbor(value::Value),
+}
+
+impl Body {
+    /// The Content-Type the body is sent with unless the headers set one.
+    /// Multipart bodies always set their own for the boundary, and may not
+    /// be given one in the headers.
+    pub fn content_type(&self) -> &str {
+        match self {
+            Body::Json(_) => "application/json",
+            Body::Form(_) => "application/x-www-form-urlencoded",
+            Body::Multipart(_) => "multipart/form-data",
+            Body::Text(text) => &text.content_type,
+            Body::File(file) => file.content_type(),
+            Body::Xml(_) => "application/xml",
+            Body::MsgPack(_) => "application/msgpack",
+            Body::Cbor(_) => "application/cbor",
+        }
+    }
+}
+
+/// Form fields and files sent as `multipart/form-data`.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Multipart {
+    #[serde(default, deserialize_with = "deserialize_fields")]
+    pub fields: Option<value::Value>,
+    /// Read from disk at send time, one part per file.
+    #[serde(default)]
+    pub files: IndexMap<String, File>,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Text {
+    #[serde(default = "Text::default_content_type")]
+    pub content_type: String,
+    #[serde(deserialize_with = "deserialize_value")]
+    pub value: value::Value,
+}
+
+impl Text {
+    fn default_content_type() -> String {
+        String::from("text/plain; charset=utf-8")
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct File {
+    /// Relative to the working directory.
+    pub path: PathBuf,
+    content_type: Option<String>,
+}
+
+impl File {
+    pub fn content_type(&self) -> &str {
+        self.content_type
+            .as_deref()
+            .unwrap_or("application/octet-stream")
+    }
+
+    /// The name a multipart part gives the file, taken from its path.
+    pub fn file_name(&self) -> Option<String> {
+        self.path
+            .file_name()
+            .map(|name| {
+                name.to_string_lossy()
+                    .into_owned()
+            })
+    }
+}
+
+fn deserialize_value<'de, D>(deserializer: D) -> Result<value::Value, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let entry = serde_yml::Value::deserialize(deserializer)?;
+
+    value::Value::from_entry(&entry).map_err(|err| err.to_de_error())
+}
+
+fn deserialize_fields<'de, D>(deserializer: D) -> Result<Option<value::Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    fields(deserialize_value(deserializer)?).map(Some)
+}
+
+/// Checks that form fields are an object of scalars or arrays of scalars.
+fn fields<E>(value: value::Value) -> Result<value::Value, E>
+where
+    E: serde::de::Error,
+{
+    let value::Value::Object(fields) = &value else {
+        return Err(Error::custom("form fields must be an object"));
+    };
+
+    if let Some((name, _)) = fields
+        .iter()
+        .find(|(_, value)| !is_flat(value))
+    {
+        return Err(Error::custom(format!(
+            "form field `{name}` must be a scalar or an array of scalars"
+        )));
+    }
+
+    Ok(value)
 }
 
 struct BodyVisitor;
@@ -137,26 +675,94 @@ impl<'de> Visitor<'de> for BodyVisitor {
     where
         A: serde::de::MapAccess<'de>,
     {
-        let entry: (String, serde_yml::Mapping) = map
-            .next_entry()?
+        let content_type: String = map
+            .next_key()?
             .ok_or(Error::custom("invalid body field"))?;
 
-        let content_type = entry.0.as_str();
-
-        return match content_type {
+        let body = match content_type.as_str() {
             "json" => {
-                let value = value::Value::from_entry(&entry.1);
+                let entry: serde_yml::Value = map.next_value()?;
 
-                match value {
-                    Ok(json_value) => Ok(Body::Json(json_value)),
-                    Err(err) => Err(err.to_de_error()),
+                value::Value::from_entry(&entry)
+                    .map(Body::Json)
+                    .map_err(|err| err.to_de_error())?
+            }
+            "form" => {
+                let entry: serde_yml::Value = map.next_value()?;
+                let value = value::Value::from_entry(&entry).map_err(|err| err.to_de_error())?;
+
+                Body::Form(fields(value)?)
+            }
+            "xml" => {
+                let entry: serde_yml::Value = map.next_value()?;
+                let value = value::Value::from_entry(&entry).map_err(|err| err.to_de_error())?;
+
+                let has_root = match &value {
+                    value::Value::Object(root) if root.len() == 1 => {
+                        root.iter()
+                            .all(|(name, value)| {
+                                !name.starts_with(['@', '#'])
+                                    && !matches!(value, value::Value::Array(_))
+                            })
+                    }
+                    _ => false,
+                };
+
+                if !has_root {
+                    return Err(Error::custom(
+                        "an xml body must be an object with a single root element",
+                    ));
                 }
+
+                Body::Xml(value)
             }
-            value => Err(Error::unknown_field(value, &["json"])),
+            "msgpack" => {
+                let entry: serde_yml::Value = map.next_value()?;
+
+                value::Value::from_entry(&entry)
+                    .map(Body::MsgPack)
+                    .map_err(|err| err.to_de_error())?
+            }
+            "cbor" => {
+                let entry: serde_yml::Value = map.next_value()?;
+
+                value::Value::from_entry(&entry)
+                    .map(Body::Cbor)
+                    .map_err(|err| err.to_de_error())?
+            }
+            "multipart" => Body::Multipart(map.next_value()?),
+            "text" => Body::Text(map.next_value()?),
+            "file" => Body::File(map.next_value()?),
+            value => return Err(Error::unknown_field(value, Self::CONTENT_TYPES)),
         };
+
+        if map
+            .next_key::<String>()?
+            .is_some()
+        {
+            return Err(Error::custom(format!(
+                "a body has only one of {}",
+                Self::CONTENT_TYPES.join(", ")
+            )));
+        }
+
+        Ok(body)
     }
 }
 
+impl BodyVisitor {
+    const CONTENT_TYPES: &[&str] = &[
+        "json",
+        "form",
+        "multipart",
+        "text",
+        "file",
+        "xml",
+        "msgpack",
+        "cbor",
+    ];
+}
+
 impl<'de> Deserialize<'de> for Body {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -169,6 +775,7 @@ impl<'de> Deserialize<'de> for Body {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::schedule::{Schedule, Zone};
     use crate::config::source::Source;
     use crate::config::value::Value;
     use serde_yml;
@@ -180,12 +787,25 @@ mod tests {
         success_deserialize_method("DELETE", Method::Delete);
         success_deserialize_method("PATCH", Method::Patch);
         success_deserialize_method("PUT", Method::Put);
+        success_deserialize_method("HEAD", Method::Head);
+        success_deserialize_method("OPTIONS", Method::Options);
+        success_deserialize_method("TRACE", Method::Trace);
+        success_deserialize_method("CONNECT", Method::Connect);
+        success_deserialize_method("PROPFIND", Method::Custom(String::from("PROPFIND")));
+        success_deserialize_method(
+            "VERSION-CONTROL",
+            Method::Custom(String::from("VERSION-CONTROL")),
+        );
 
         failure_deserialize_method("get");
         failure_deserialize_method("post");
         failure_deserialize_method("delete");
         failure_deserialize_method("patch");
         failure_deserialize_method("put");
+        failure_deserialize_method("head");
+        failure_deserialize_method("Propfind");
+        failure_deserialize_method("MK COL");
+        failure_deserialize_method("\"\"");
     }
 
     fn success_deserialize_method(input: &str, expected: Method) {
@@ -202,9 +822,249 @@ mod tests {
         assert!(is_err)
     }
 
+    #[test]
+    fn test_deserialize_status_code() {
+        let status_code = |input: &str| serde_yml::from_str::<StatusCode>(input);
+
+        assert_eq!(StatusCode::from(204), status_code("204").unwrap());
+        assert_eq!(StatusCode::from(204), status_code("\"204\"").unwrap());
+        assert_eq!(
+            StatusCode {
+                codes: 200..=299,
+                negated: false,
+            },
+            status_code("2xx").unwrap()
+        );
+        assert_eq!(
+            StatusCode {
+                codes: 200..=206,
+                negated: false,
+            },
+            status_code("200-206").unwrap()
+        );
+        assert_eq!(
+            StatusCode {
+                codes: 204..=204,
+                negated: true,
+            },
+            status_code("\"!204\"").unwrap()
+        );
+        assert_eq!(
+            StatusCode {
+                codes: 400..=499,
+                negated: true,
+            },
+            status_code("\"!4xx\"").unwrap()
+        );
+
+        let failure = |input: &str, expected: &str| {
+            let err = status_code(input)
+                .err()
+                .unwrap()
+                .to_string();
+
+            assert!(err.contains(expected), "{err}");
+        };
+
+        failure("42", "status code 42 is not between 100 and 599");
+        failure("-1", "status code -1 is not between 100 and 599");
+        failure(
+            "ok",
+            "invalid status code \"ok\", expected e.g. 200, 2xx, 200-299 or !204",
+        );
+        failure(
+            "6xx",
+            "invalid status class \"6xx\", expected one of 1xx to 5xx",
+        );
+        failure("299-200", "status code range \"299-200\" is empty");
+        failure("200-", "invalid status code \"\"");
+    }
+
+    #[test]
+    fn test_is_success_status() {
+        let with_codes = |codes: &str| {
+            serde_yml::from_str::<Request>(&format!(
+                "
+                method: GET
+                url: http://localhost:3030/load
+                success_status_codes: {codes}"
+            ))
+            .unwrap()
+        };
+
+        let request = with_codes("[]");
+        assert!(request.is_success_status(200));
+        assert!(!request.is_success_status(201));
+
+        let request = with_codes("[2xx, \"!204\", 304]");
+        assert!(request.is_success_status(201));
+        assert!(request.is_success_status(304));
+        assert!(!request.is_success_status(204));
+        assert!(!request.is_success_status(302));
+
+        let request = with_codes("[\"!201-299\"]");
+        assert!(request.is_success_status(200));
+        assert!(!request.is_success_status(204));
+    }
+
+    #[test]
+    fn test_deserialize_query() {
+        let query: Query = serde_yml::from_str(
+            "
+            since:
+              type: source
+              source: last_execute_time
+            tag:
+              type: array
+              items:
+                - type: string
+                  value: a
+                - type: integer
+                  value: 1",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Query(IndexMap::from_iter([
+                (
+                    String::from("since"),
+                    Value::Source(Source::LastExecuteDate)
+                ),
+                (
+                    String::from("tag"),
+                    Value::Array(vec![Value::String(String::from("a")), Value::Integer(1)])
+                ),
+            ])),
+            query
+        );
+
+        let err = serde_yml::from_str::<Query>(
+            "
+            filter:
+              type: object
+              properties: {}",
+        )
+        .err()
+        .unwrap();
+
+        assert!(err
+            .to_string()
+            .contains("query parameter `filter` must be a scalar or an array of scalars"));
+    }
+
+    #[test]
+    fn test_deserialize_body() {
+        let body = |input: &str| serde_yml::from_str::<Body>(input);
+
+        assert_eq!(
+            Body::Form(Value::Object(IndexMap::from_iter([(
+                String::from("kind"),
+                Value::String(String::from("daily"))
+            )]))),
+            body(
+                "
+                form:
+                  type: object
+                  properties:
+                    kind:
+                      type: string
+                      value: daily"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            Body::Text(Text {
+                content_type: String::from("text/plain; charset=utf-8"),
+                value: Value::String(String::from("hello")),
+            }),
+            body("text: {value: {type: string, value: hello}}").unwrap()
+        );
+        assert_eq!(
+            Body::Multipart(Multipart {
+                fields: None,
+                files: IndexMap::from_iter([(
+                    String::from("report"),
+                    File {
+                        path: PathBuf::from("exports/report.csv"),
+                        content_type: Some(String::from("text/csv")),
+                    }
+                )]),
+            }),
+            body(
+                "
+                multipart:
+                  files:
+                    report: {path: exports/report.csv, content_type: text/csv}"
+            )
+            .unwrap()
+        );
+
+        let file = body("file: {path: /tmp/data.bin}").unwrap();
+        assert_eq!("application/octet-stream", file.content_type());
+
+        let failure = |input: &str, expected: &str| {
+            let err = body(input)
+                .err()
+                .unwrap()
+                .to_string();
+
+            assert!(err.contains(expected), "{err}");
+        };
+
+        failure(
+            "form: {type: string, value: x}",
+            "form fields must be an object",
+        );
+        failure(
+            "
+            multipart:
+              fields:
+                type: object
+                properties:
+                  nested: {type: object, properties: {}}",
+            "form field `nested` must be a scalar or an array of scalars",
+        );
+        failure(
+            "file: {path: a.bin}\ntext: {value: {type: null}}",
+            "a body has only one of",
+        );
+        failure("yaml: {type: null}", "unknown field `yaml`");
+        failure(
+            "
+            xml:
+              type: object
+              properties:
+                order: {type: string, value: a}
+                invoice: {type: string, value: b}",
+            "an xml body must be an object with a single root element",
+        );
+        failure("file: {name: a.bin}", "unknown field `name`");
+    }
+
+    #[test]
+    fn test_deserialize_request_rejects_multipart_content_type() {
+        let err = serde_yml::from_str::<Request>(
+            "
+            method: POST
+            url: http://localhost:3030/upload
+            headers:
+              content-type: multipart/form-data
+            body:
+              multipart:
+                files:
+                  report: {path: exports/report.csv}",
+        )
+        .err()
+        .unwrap();
+
+        assert!(err
+            .to_string()
+            .contains("a multipart body sets its own Content-Type with the boundary"));
+    }
+
     #[test]
     fn test_deserialize_task() {
-        let headers = HashMap::from_iter([
+        let headers = IndexMap::from_iter([
             (
                 String::from("X-Api-Key"),
                 Value::String(String::from("env!(YOUR_OWN_SERVICE_KEY)")),
@@ -223,11 +1083,11 @@ mod tests {
             ),
         ]);
 
-        let json_value = Value::Object(HashMap::from_iter([
+        let json_value = Value::Object(IndexMap::from_iter([
             (String::from("field1"), Value::String(String::from("hello"))),
             (
                 String::from("field2"),
-                Value::Object(HashMap::from_iter([(
+                Value::Object(IndexMap::from_iter([(
                     String::from("field1_1"),
                     Value::Integer(100),
                 )])),
@@ -235,7 +1095,7 @@ mod tests {
             (
                 String::from("field3"),
                 Value::Array(vec![
-                    Value::Object(HashMap::from_iter([(
+                    Value::Object(IndexMap::from_iter([(
                         String::from("field1"),
                         Value::Bool(false),
                     )])),
@@ -260,6 +1120,7 @@ mod tests {
           name: load_data # required
           method: GET # required
           url: http://localhost:3030/load # required
+          schedule: \"*/5 * * * *\" # required
           headers: # optional, default is empty
             X-Api-Key:
               type: string
@@ -308,11 +1169,31 @@ mod tests {
                   source: execute_time # this add string field with date in RFC3339",
             Task {
                 name: String::from("load_data"),
-                method: Method::Get,
-                url: Url::parse("http://localhost:3030/load").unwrap(),
-                headers: Headers(headers),
-                success_status_codes: vec![200],
-                body: Some(body),
+                request: Request {
+                    method: Method::Get,
+                    url: Endpoint::Fixed(Url::parse("http://localhost:3030/load").unwrap()),
+                    headers: Headers(headers),
+                    query: Query::default(),
+                    success_status_codes: vec![StatusCode::from(200)],
+                    body: Some(body),
+                },
+                extract: HashMap::new(),
+                pagination: None,
+                assertions: vec![],
+                options: tasks::Options {
+                    schedule: Some(Schedule::Cron(
+                        "*/5 * * * *".parse().unwrap(),
+                        Zone::default(),
+                    )),
+                    depends_on: vec![],
+                    trigger_rule: tasks::TriggerRule::AllSuccess,
+                    retry: None,
+                    misfire: tasks::Misfire::Skip,
+                    misfire_grace: std::time::Duration::from_secs(60),
+                    timeout: std::time::Duration::from_secs(30),
+                    concurrency: tasks::Concurrency::Allow,
+                    queue_limit: 10,
+                },
             },
         );
     }
@@ -1,12 +1,477 @@
+pub mod assertion;
+pub mod duration;
+pub mod extract;
 pub mod http;
+pub mod pagination;
+pub mod pipeline;
+pub mod retry;
+pub mod schedule;
 pub mod source;
+pub mod store;
 pub mod tasks;
+pub mod template;
 pub mod value;
+use crate::yaml;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Yaml(serde_yml::Error),
+    Env(yaml::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "failed to read config: {err}"),
+            Error::Yaml(err) => write!(f, "invalid config: {err}"),
+            Error::Env(err) => write!(f, "invalid config: {err}"),
+            Error::Invalid(reason) => write!(f, "invalid config: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
 
 #[derive(Deserialize)]
 pub struct Config {
+    #[serde(default)]
+    store: store::Store,
     tasks: Vec<tasks::Task>,
 }
 
-impl Config {}
+impl Config {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let content = fs::read_to_string(path).map_err(Error::Io)?;
+
+        content.parse()
+    }
+
+    pub fn store(&self) -> &store::Store {
+        &self.store
+    }
+
+    pub fn into_tasks(self) -> Vec<tasks::Task> {
+        self.tasks
+    }
+
+    /// Checks what a single task cannot: unique names, and that the
+    /// `depends_on` names exist and form no cycle.
+    fn validate(&self) -> Result<(), String> {
+        let mut indices = HashMap::new();
+
+        for (index, task) in self.tasks.iter().enumerate() {
+            if indices
+                .insert(task.name(), index)
+                .is_some()
+            {
+                return Err(format!("duplicate task name `{}`", task.name()));
+            }
+        }
+
+        for task in &self.tasks {
+            task.validate()?;
+
+            let options = task.options();
+
+            match (&options.schedule, options.depends_on.is_empty()) {
+                (None, true) => {
+                    return Err(format!(
+                        "task `{}` is missing a schedule, expected one of `schedule`, `every`, `at` or `depends_on`",
+                        task.name()
+                    ))
+                }
+                (Some(_), false) => {
+                    return Err(format!(
+                        "task `{}` runs after its `depends_on` and cannot also have a schedule",
+                        task.name()
+                    ))
+                }
+                _ => {}
+            }
+
+            if let Some(missing) = options
+                .depends_on
+                .iter()
+                .find(|name| !indices.contains_key(name.as_str()))
+            {
+                return Err(format!(
+                    "task `{}` depends on unknown task `{missing}`",
+                    task.name()
+                ));
+            }
+        }
+
+        // Depth-first search, `path` holds the tasks being visited.
+        let mut visited = vec![false; self.tasks.len()];
+        let mut path = Vec::new();
+
+        fn visit(
+            tasks: &[tasks::Task],
+            indices: &HashMap<&str, usize>,
+            index: usize,
+            visited: &mut [bool],
+            path: &mut Vec<usize>,
+        ) -> Result<(), String> {
+            if let Some(start) = path
+                .iter()
+                .position(|visiting| *visiting == index)
+            {
+                let cycle: Vec<&str> = path[start..]
+                    .iter()
+                    .chain([&index])
+                    .map(|index| tasks[*index].name())
+                    .collect();
+
+                return Err(format!("dependency cycle {}", cycle.join(" -> ")));
+            }
+
+            if visited[index] {
+                return Ok(());
+            }
+
+            path.push(index);
+
+            for upstream in &tasks[index]
+                .options()
+                .depends_on
+            {
+                visit(tasks, indices, indices[upstream.as_str()], visited, path)?;
+            }
+
+            path.pop();
+            visited[index] = true;
+
+            Ok(())
+        }
+
+        for index in 0..self.tasks.len() {
+            visit(&self.tasks, &indices, index, &mut visited, &mut path)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Config {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: serde_yml::Value = serde_yml::from_str(s).map_err(Error::Yaml)?;
+        let value = yaml::recursive_replace_env(value).map_err(Error::Env)?;
+
+        let config = Config::deserialize(value).map_err(Error::Yaml)?;
+        config
+            .validate()
+            .map_err(Error::Invalid)?;
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config: Config = "
+          tasks:
+            - type: http
+              name: load_data
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"*/5 * * * *\"
+            - type: http
+              name: push_data
+              method: POST
+              url: http://localhost:3030/push
+              schedule: \"@daily\""
+            .parse()
+            .unwrap();
+
+        let names: Vec<&str> = config
+            .tasks
+            .iter()
+            .map(|task| task.name())
+            .collect();
+
+        assert_eq!(vec!["load_data", "push_data"], names);
+        assert_eq!(&store::Store::default(), config.store());
+    }
+
+    #[test]
+    fn test_parse_config_tags() {
+        let config: Config = "
+          tasks:
+            - type: http
+              name: load_data
+              method: POST
+              url: !template \"http://localhost:3030/load/{{ execute_time | date('%F') }}\"
+              schedule: \"@daily\"
+              headers:
+                X-Since: !source {source: last_execute_time, format: unix}
+              body:
+                json:
+                  until: !source execute_time"
+            .parse()
+            .unwrap();
+
+        let tasks = config.into_tasks();
+        let tasks::Task::Http(task) = &tasks[0] else {
+            panic!("expected an http task");
+        };
+
+        assert!(matches!(
+            task.request().url(),
+            http::Endpoint::Value(value::Value::Template(_))
+        ));
+        assert_eq!(3, task.request().values().len());
+    }
+
+    #[test]
+    fn test_parse_config_store() {
+        let config: Config = "
+          store:
+            type: sqlite
+            path: /var/lib/scheduler/state.db
+          tasks: []"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            &store::Store::Sqlite {
+                path: "/var/lib/scheduler/state.db".into()
+            },
+            config.store()
+        );
+    }
+
+    #[test]
+    fn test_parse_config_unknown_task_type() {
+        let err = "
+          tasks:
+            - type: ftp
+              name: load_data"
+            .parse::<Config>()
+            .err();
+
+        assert!(matches!(err, Some(Error::Yaml(_))));
+    }
+
+    #[test]
+    fn test_parse_config_invalid_schedule() {
+        let err = "
+          tasks:
+            - type: http
+              name: load_data
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"*/5 * * *\""
+            .parse::<Config>()
+            .err()
+            .unwrap();
+
+        assert!(err
+            .to_string()
+            .contains("invalid cron expression \"*/5 * * *\""));
+    }
+
+    #[test]
+    fn test_parse_config_dependencies() {
+        let config: Config = "
+          tasks:
+            - type: http
+              name: load_data
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"*/5 * * * *\"
+            - type: http
+              name: transform
+              method: POST
+              url: http://localhost:3030/transform
+              depends_on: [load_data]
+              trigger_rule: all_done"
+            .parse()
+            .unwrap();
+
+        let options = config.tasks[1].options();
+
+        assert_eq!(None, options.schedule);
+        assert_eq!(vec![String::from("load_data")], options.depends_on);
+        assert_eq!(tasks::TriggerRule::AllDone, options.trigger_rule);
+    }
+
+    #[test]
+    fn test_parse_config_invalid_dependencies() {
+        failure_parse_config(
+            "
+              - type: http
+                name: transform
+                method: POST
+                url: http://localhost:3030/transform
+                depends_on: [load_data]",
+            "task `transform` depends on unknown task `load_data`",
+        );
+        failure_parse_config(
+            "
+              - type: http
+                name: load_data
+                method: GET
+                url: http://localhost:3030/load
+                depends_on: [transform]
+              - type: http
+                name: transform
+                method: POST
+                url: http://localhost:3030/transform
+                depends_on: [push_data]
+              - type: http
+                name: push_data
+                method: POST
+                url: http://localhost:3030/push
+                depends_on: [transform]",
+            "dependency cycle transform -> push_data -> transform",
+        );
+        failure_parse_config(
+            "
+              - type: http
+                name: load_data
+                method: GET
+                url: http://localhost:3030/load
+                depends_on: [load_data]",
+            "dependency cycle load_data -> load_data",
+        );
+        failure_parse_config(
+            "
+              - type: http
+                name: load_data
+                method: GET
+                url: http://localhost:3030/load",
+            "task `load_data` is missing a schedule",
+        );
+        failure_parse_config(
+            "
+              - type: http
+                name: load_data
+                method: GET
+                url: http://localhost:3030/load
+                schedule: \"@daily\"
+              - type: http
+                name: load_data
+                method: GET
+                url: http://localhost:3030/load
+                schedule: \"@daily\"",
+            "duplicate task name `load_data`",
+        );
+        failure_parse_config(
+            "
+              - type: http
+                name: load_data
+                method: GET
+                url: http://localhost:3030/load
+                schedule: \"@daily\"
+                headers:
+                  X-Token:
+                    type: step
+                    step: login
+                    field: token",
+            "task `load_data` uses a `step` value, which only pipeline steps can",
+        );
+        failure_parse_config(
+            "
+              - type: http
+                name: load_data
+                method: GET
+                url: http://localhost:3030/load
+                schedule: \"@daily\"
+                headers:
+                  X-Cursor:
+                    type: source
+                    source: state.cursor",
+            "task `load_data` reads `state.cursor`, which it does not extract",
+        );
+        failure_parse_config(
+            "
+              - type: http
+                name: load_data
+                method: GET
+                url:
+                  type: template
+                  value: \"http://localhost:3030/load/{{ state.page }}\"
+                schedule: \"@daily\"",
+            "task `load_data` reads `state.page`, which it does not extract",
+        );
+    }
+
+    #[test]
+    fn test_parse_config_invalid_date_format() {
+        let failure = |tasks: &str, expected: &str| {
+            let err = format!("tasks:{tasks}")
+                .parse::<Config>()
+                .err()
+                .unwrap();
+
+            assert!(
+                err.to_string()
+                    .contains(expected),
+                "{err}"
+            );
+        };
+        let task = |options: &str| {
+            format!(
+                "
+              - type: http
+                name: load_data
+                method: GET
+                url: http://localhost:3030/load
+                schedule: \"@daily\"
+                headers:
+                  X-Since:
+                    type: source
+                    {options}"
+            )
+        };
+
+        failure(
+            &task("source: execute_time\n                    format: iso"),
+            "invalid date format \"iso\", expected rfc3339, unix, unix_ms or a strftime pattern",
+        );
+        failure(
+            &task("source: execute_time\n                    timezone: Mars/Olympus"),
+            "unknown timezone \"Mars/Olympus\", expected an IANA name such as Europe/Berlin",
+        );
+        failure(
+            &task("source: execute_time\n                    offset: yesterday"),
+            "invalid duration \"yesterday\"",
+        );
+        failure(
+            &task("source: execute_time\n                    truncate: week"),
+            "invalid truncate \"week\", expected day",
+        );
+        failure(
+            &task("source: state.since\n                    format: unix"),
+            "`format`, `timezone`, `offset` and `truncate` only apply to execute_time and last_execute_time",
+        );
+    }
+
+    fn failure_parse_config(tasks: &str, expected: &str) {
+        let err = format!("tasks:{tasks}")
+            .parse::<Config>()
+            .err()
+            .unwrap();
+
+        assert!(matches!(err, Error::Invalid(_)), "{err:?}");
+        assert!(
+            err.to_string()
+                .contains(expected),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use super::extract::Path;
+use serde::Deserialize;
+use std::num::NonZeroUsize;
+
+/// How an http task walks a paged response. Every page is requested in
+/// turn until the last one or `max_pages`, whichever comes first.
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct Pagination {
+    #[serde(flatten)]
+    pub style: Style,
+    /// Guards against APIs that never report a last page.
+    #[serde(default = "Pagination::default_max_pages")]
+    pub max_pages: NonZeroUsize,
+}
+
+impl Pagination {
+    fn default_max_pages() -> NonZeroUsize {
+        NonZeroUsize::new(100).unwrap()
+    }
+
+    /// The array every page holds, if the style knows of one.
+    pub fn items(&self) -> Option<&Path> {
+        match &self.style {
+            Style::Cursor { items, .. } | Style::Link { items } => items.as_ref(),
+            Style::Page { items, .. } | Style::Offset { items, .. } => Some(items),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Style {
+    /// Sends the cursor read from each page as `param` of the next one,
+    /// until a page has none.
+    Cursor {
+        cursor: Path,
+        param: String,
+        items: Option<Path>,
+    },
+    /// Counts `param` up from `start` until a page holds no items, or fewer
+    /// than `size`.
+    Page {
+        #[serde(default = "Style::default_page_param")]
+        param: String,
+        #[serde(default = "Style::default_start")]
+        start: u64,
+        /// Sent as `size_param` when both are set.
+        size: Option<u64>,
+        size_param: Option<String>,
+        items: Path,
+    },
+    /// Moves `param` on by the items of every page until a page holds
+    /// fewer than `limit`.
+    Offset {
+        #[serde(default = "Style::default_offset_param")]
+        param: String,
+        #[serde(default = "Style::default_limit_param")]
+        limit_param: String,
+        limit: u64,
+        items: Path,
+    },
+    /// Follows the `Link` header's `rel="next"` url until a page has none.
+    /// A link to another origin fails the run rather than carry the headers there.
+    Link { items: Option<Path> },
+}
+
+impl Style {
+    fn default_page_param() -> String {
+        String::from("page")
+    }
+
+    fn default_start() -> u64 {
+        1
+    }
+
+    fn default_offset_param() -> String {
+        String::from("offset")
+    }
+
+    fn default_limit_param() -> String {
+        String::from("limit")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(path: &str) -> Path {
+        path.parse().unwrap()
+    }
+
+    #[test]
+    fn test_deserialize_pagination() {
+        assert_eq!(
+            Pagination {
+                style: Style::Cursor {
+                    cursor: path("$.next_page_token"),
+                    param: String::from("page_token"),
+                    items: None,
+                },
+                max_pages: NonZeroUsize::new(100).unwrap(),
+            },
+            serde_yml::from_str::<Pagination>(
+                "
+                type: cursor
+                cursor: $.next_page_token
+                param: page_token"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            Pagination {
+                style: Style::Page {
+                    param: String::from("page"),
+                    start: 1,
+                    size: Some(50),
+                    size_param: Some(String::from("per_page")),
+                    items: path("/data"),
+                },
+                max_pages: NonZeroUsize::new(10).unwrap(),
+            },
+            serde_yml::from_str::<Pagination>(
+                "
+                type: page
+                size: 50
+                size_param: per_page
+                items: /data
+                max_pages: 10"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            Pagination {
+                style: Style::Offset {
+                    param: String::from("offset"),
+                    limit_param: String::from("limit"),
+                    limit: 200,
+                    items: path("$.items"),
+                },
+                max_pages: NonZeroUsize::new(100).unwrap(),
+            },
+            serde_yml::from_str::<Pagination>(
+                "
+                type: offset
+                limit: 200
+                items: $.items"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            Pagination {
+                style: Style::Link { items: None },
+                max_pages: NonZeroUsize::new(100).unwrap(),
+            },
+            serde_yml::from_str::<Pagination>("type: link").unwrap()
+        );
+
+        assert!(serde_yml::from_str::<Pagination>("type: page").is_err());
+        assert!(serde_yml::from_str::<Pagination>("type: link\nmax_pages: 0").is_err());
+        assert!(serde_yml::from_str::<Pagination>("type: scroll").is_err());
+    }
+}
@@ -0,0 +1,291 @@
+use crate::config::{assertion, extract, http, retry, tasks};
+use serde::de::{Error, IgnoredAny};
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashMap};
+
+/// Runs its steps in order, each one after the previous succeeded.
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct Task {
+    name: String,
+    steps: Vec<Step>,
+    #[serde(flatten)]
+    options: tasks::Options,
+}
+
+impl Task {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn options(&self) -> &tasks::Options {
+        &self.options
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Checks that step names are unique and that every `step` value refers
+    /// to a field extracted by an earlier step.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.steps.is_empty() {
+            return Err(format!("pipeline `{}` has no steps", self.name));
+        }
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let earlier = &self.steps[..index];
+
+            if earlier
+                .iter()
+                .any(|earlier| earlier.name == step.name)
+            {
+                return Err(format!(
+                    "pipeline `{}` has more than one step named `{}`",
+                    self.name, step.name
+                ));
+            }
+
+            for (name, field) in step
+                .request
+                .values()
+                .into_iter()
+                .flat_map(|value| value.step_fields())
+            {
+                let extracted = earlier
+                    .iter()
+                    .find(|earlier| earlier.name == name)
+                    .is_some_and(|earlier| {
+                        earlier
+                            .extract
+                            .contains_key(field)
+                    });
+
+                if !extracted {
+                    return Err(format!(
+                        "step `{}` uses field `{field}` of step `{name}`, which no earlier step extracts",
+                        step.name
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(remote = "Self")]
+pub struct Step {
+    name: String,
+    #[serde(flatten)]
+    request: http::Request,
+    /// Fields taken from the JSON response for later steps.
+    #[serde(default)]
+    extract: HashMap<String, extract::Extract>,
+    /// Checks the response must pass, beyond its status.
+    #[serde(default, rename = "assert")]
+    assertions: Vec<assertion::Assertion>,
+    /// Replaces the pipeline's `retry` for this step.
+    retry: Option<retry::Retry>,
+    /// Whatever else the step sets, rejected once deserialized.
+    #[serde(flatten)]
+    unknown: BTreeMap<String, IgnoredAny>,
+}
+
+impl<'de> Deserialize<'de> for Step {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let step = Step::deserialize(deserializer)?;
+
+        match step
+            .unknown
+            .keys()
+            .next()
+            .map(String::as_str)
+        {
+            None => Ok(step),
+            Some("pagination") => Err(Error::custom(format!(
+                "step `{}` sets `pagination`, which only http tasks can",
+                step.name
+            ))),
+            Some(field) => Err(Error::custom(format!(
+                "step `{}` has unknown field `{field}`",
+                step.name
+            ))),
+        }
+    }
+}
+
+impl Step {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn request(&self) -> &http::Request {
+        &self.request
+    }
+
+    pub fn extract(&self) -> &HashMap<String, extract::Extract> {
+        &self.extract
+    }
+
+    pub fn assertions(&self) -> &[assertion::Assertion] {
+        &self.assertions
+    }
+
+    pub fn retry(&self) -> Option<&retry::Retry> {
+        self.retry.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pipeline(steps: &str) -> Result<(), String> {
+        let task: Task = serde_yml::from_str(&format!(
+            "
+            name: sync
+            schedule: \"@daily\"
+            steps:{steps}"
+        ))
+        .unwrap();
+
+        task.validate()
+    }
+
+    #[test]
+    fn test_validate_pipeline() {
+        assert_eq!(
+            Err(String::from(
+                "step `load` uses field `next` of step `login`, which no earlier step extracts"
+            )),
+            pipeline(
+                "
+              - name: login
+                method: POST
+                url: http://localhost:3030/login
+                extract:
+                  token: /access_token
+              - name: load
+                method: GET
+                url:
+                  type: step
+                  step: login
+                  field: next"
+            )
+        );
+
+        assert_eq!(
+            Ok(()),
+            pipeline(
+                "
+              - name: login
+                method: POST
+                url: http://localhost:3030/login
+                extract:
+                  token: /access_token
+              - name: load
+                method: GET
+                url: http://localhost:3030/load
+                headers:
+                  X-Token:
+                    type: step
+                    step: login
+                    field: token"
+            )
+        );
+
+        assert_eq!(
+            Err(String::from(
+                "step `login` uses field `token` of step `login`, which no earlier step extracts"
+            )),
+            pipeline(
+                "
+              - name: login
+                method: POST
+                url: http://localhost:3030/login
+                extract:
+                  token: /access_token
+                body:
+                  json:
+                    type: step
+                    step: login
+                    field: token"
+            )
+        );
+
+        assert_eq!(
+            Err(String::from(
+                "pipeline `sync` has more than one step named `login`"
+            )),
+            pipeline(
+                "
+              - name: login
+                method: POST
+                url: http://localhost:3030/login
+              - name: login
+                method: POST
+                url: http://localhost:3030/login"
+            )
+        );
+
+        assert!(pipeline(" []").is_err());
+    }
+
+    #[test]
+    fn test_deserialize_step_rejects_unknown_fields() {
+        let step = |fields: &str| {
+            serde_yml::from_str::<Step>(&format!(
+                "
+                name: load
+                method: GET
+                url: http://localhost:3030/load{fields}"
+            ))
+            .map_err(|err| err.to_string())
+        };
+
+        let loaded = step(
+            "
+                extract:
+                  next:
+                    path: /next
+                    default: null
+                assert:
+                  - json: $.status
+                    equals: ok
+                retry:
+                  max_attempts: 3",
+        )
+        .unwrap();
+        assert_eq!(1, loaded.assertions().len());
+        assert!(loaded.retry().is_some());
+        assert_eq!(
+            Some(serde_json::Value::Null),
+            loaded.extract()["next"].default
+        );
+
+        let err = step(
+            "
+                pagination:
+                  type: link",
+        )
+        .unwrap_err();
+        assert!(
+            err.contains("step `load` sets `pagination`, which only http tasks can"),
+            "{err}"
+        );
+
+        let err = step(
+            "
+                every: 1h",
+        )
+        .unwrap_err();
+        assert!(
+            err.contains("step `load` has unknown field `every`"),
+            "{err}"
+        );
+    }
+}
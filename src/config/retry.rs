@@ -0,0 +1,159 @@
+use super::duration;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use std::time::Duration;
+
+/// How a failed run is retried before it counts as failed.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(remote = "Self")]
+pub struct Retry {
+    /// Attempts including the first one.
+    #[serde(default = "Retry::default_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(
+        default = "Retry::default_initial_delay",
+        deserialize_with = "duration::deserialize"
+    )]
+    pub initial_delay: Duration,
+    #[serde(
+        default = "Retry::default_max_delay",
+        deserialize_with = "duration::deserialize"
+    )]
+    pub max_delay: Duration,
+    #[serde(default = "Retry::default_multiplier")]
+    pub multiplier: f64,
+    #[serde(default)]
+    pub jitter: Jitter,
+    /// Statuses worth another attempt. Connection errors are always retried.
+    #[serde(default = "Retry::default_status_codes")]
+    pub status_codes: Vec<u16>,
+}
+
+impl<'de> Deserialize<'de> for Retry {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let retry = Retry::deserialize(deserializer)?;
+
+        if retry.max_attempts == 0 {
+            return Err(Error::custom("`max_attempts` must be at least 1"));
+        }
+
+        if !retry.multiplier.is_finite() || retry.multiplier < 1.0 {
+            return Err(Error::custom(format!(
+                "`multiplier` must be a finite number of at least 1, got {}",
+                retry.multiplier
+            )));
+        }
+
+        if retry.initial_delay > retry.max_delay {
+            return Err(Error::custom(
+                "`initial_delay` must not be longer than `max_delay`",
+            ));
+        }
+
+        Ok(retry)
+    }
+}
+
+impl Retry {
+    fn default_max_attempts() -> u32 {
+        3
+    }
+
+    fn default_initial_delay() -> Duration {
+        Duration::from_secs(1)
+    }
+
+    fn default_max_delay() -> Duration {
+        Duration::from_secs(60)
+    }
+
+    fn default_multiplier() -> f64 {
+        2.0
+    }
+
+    fn default_status_codes() -> Vec<u16> {
+        vec![429, 500, 502, 503, 504]
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Jitter {
+    None,
+    /// A random delay between zero and the exponential backoff.
+    #[default]
+    Full,
+    /// A random delay between the initial delay and three times the previous one.
+    Decorrelated,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_retry() {
+        assert_eq!(
+            Retry {
+                max_attempts: 3,
+                initial_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(60),
+                multiplier: 2.0,
+                jitter: Jitter::Full,
+                status_codes: vec![429, 500, 502, 503, 504],
+            },
+            serde_yml::from_str::<Retry>("{}").unwrap()
+        );
+
+        assert_eq!(
+            Retry {
+                max_attempts: 5,
+                initial_delay: Duration::from_millis(500),
+                max_delay: Duration::from_secs(90),
+                multiplier: 1.5,
+                jitter: Jitter::Decorrelated,
+                status_codes: vec![503],
+            },
+            serde_yml::from_str::<Retry>(
+                "
+                max_attempts: 5
+                initial_delay: 500ms
+                max_delay: 1m 30s
+                multiplier: 1.5
+                jitter: decorrelated
+                status_codes: [503]"
+            )
+            .unwrap()
+        );
+
+        let err = serde_yml::from_str::<Retry>("initial_delay: soon")
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .contains("invalid duration \"soon\""));
+    }
+
+    #[test]
+    fn test_deserialize_retry_errors() {
+        let err = |yaml: &str| {
+            serde_yml::from_str::<Retry>(yaml)
+                .err()
+                .unwrap()
+                .to_string()
+        };
+
+        assert!(err("max_attempts: 0").contains("`max_attempts` must be at least 1"));
+        assert!(err("multiplier: 0.5")
+            .contains("`multiplier` must be a finite number of at least 1, got 0.5"));
+        assert!(err("multiplier: .nan")
+            .contains("`multiplier` must be a finite number of at least 1, got NaN"));
+        assert!(err("multiplier: .inf")
+            .contains("`multiplier` must be a finite number of at least 1, got inf"));
+        assert!(err("initial_delay: 2m\nmax_delay: 1m")
+            .contains("`initial_delay` must not be longer than `max_delay`"));
+    }
+}
@@ -0,0 +1,878 @@
+use super::duration;
+use chrono::{DateTime, LocalResult, NaiveDateTime, Offset, SubsecRound, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use croner::parser::{CronParser, Seconds, Year};
+use serde::de::{Error, Visitor};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// When a task runs, declared on the task as exactly one of `schedule`,
+/// `every` or `at`.
+#[derive(Debug, PartialEq)]
+pub enum Schedule {
+    Cron(Cron, Zone),
+    Every(Interval),
+    /// Fires once. A one-shot only fires while `at` is still ahead, so a
+    /// restart after it succeeded does not fire it again. One missed while
+    /// the scheduler was down, or whose run failed, is subject to the task's
+    /// misfire policy after a restart.
+    At(DateTime<Utc>),
+}
+
+impl Schedule {
+    /// Returns the first occurrence strictly after `after`. `started_at` is
+    /// the start-up time intervals anchored to start-up count from.
+    pub fn next_after(
+        &self,
+        after: DateTime<Utc>,
+        started_at: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Cron(cron, zone) => cron.next_after(after, zone),
+            Schedule::Every(interval) => interval.next_after(after, started_at),
+            Schedule::At(at) => (*at > after).then_some(*at),
+        }
+    }
+
+    /// Returns the last occurrence at or before `until`.
+    pub fn last_until(
+        &self,
+        until: DateTime<Utc>,
+        started_at: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Cron(cron, zone) => cron.last_until(until, zone),
+            Schedule::Every(interval) => interval.last_until(until, started_at),
+            Schedule::At(at) => (*at <= until).then_some(*at),
+        }
+    }
+}
+
+/// A fixed period, phase-aligned to its anchor.
+#[derive(Debug, PartialEq)]
+pub struct Interval {
+    pub period: TimeDelta,
+    pub anchor: Anchor,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Anchor {
+    Startup,
+    Epoch(DateTime<Utc>),
+}
+
+impl Interval {
+    pub fn next_after(
+        &self,
+        after: DateTime<Utc>,
+        started_at: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        self.nth(after, started_at, 1)
+    }
+
+    pub fn last_until(
+        &self,
+        until: DateTime<Utc>,
+        started_at: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        self.nth(until, started_at, 0)
+    }
+
+    /// Returns the occurrence `skip` periods after the last one at or before
+    /// `instant`.
+    fn nth(
+        &self,
+        instant: DateTime<Utc>,
+        started_at: DateTime<Utc>,
+        skip: i128,
+    ) -> Option<DateTime<Utc>> {
+        let origin = match self.anchor {
+            Anchor::Startup => started_at,
+            Anchor::Epoch(epoch) => epoch,
+        };
+
+        let period = i128::from(
+            self.period
+                .num_nanoseconds()?,
+        );
+        let elapsed = i128::from((instant - origin).num_nanoseconds()?);
+        let periods = elapsed.div_euclid(period) + skip;
+
+        let offset = i64::try_from(periods * period).ok()?;
+
+        origin.checked_add_signed(TimeDelta::nanoseconds(offset))
+    }
+}
+
+impl FromStr for Anchor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "startup" => Ok(Anchor::Startup),
+            "epoch" => Ok(Anchor::Epoch(DateTime::UNIX_EPOCH)),
+            s => DateTime::parse_from_rfc3339(s)
+                .map(|epoch| Anchor::Epoch(epoch.to_utc()))
+                .map_err(|err| {
+                    format!(
+                        "invalid anchor \"{s}\", expected startup, epoch or RFC3339 date: {err}"
+                    )
+                }),
+        }
+    }
+}
+
+/// The time zone a cron expression is evaluated in, and how wall-clock
+/// times that a DST transition skips or repeats are resolved.
+#[derive(Debug, PartialEq)]
+pub struct Zone {
+    pub timezone: Tz,
+    pub nonexistent: Nonexistent,
+    pub ambiguous: Ambiguous,
+}
+
+impl Default for Zone {
+    fn default() -> Self {
+        Self {
+            timezone: Tz::UTC,
+            nonexistent: Nonexistent::Shift,
+            ambiguous: Ambiguous::First,
+        }
+    }
+}
+
+/// Policy for a local time skipped when clocks move forward.
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Nonexistent {
+    /// Do not run.
+    Skip,
+    /// Run as if the clock had not moved yet, i.e. later by the length of the gap.
+    Shift,
+}
+
+/// Policy for a local time repeated when clocks move back.
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Ambiguous {
+    First,
+    Last,
+    Both,
+}
+
+impl Zone {
+    /// Converts a wall-clock time into the instants it runs at.
+    pub fn resolve(&self, local: NaiveDateTime) -> Vec<DateTime<Utc>> {
+        match self
+            .timezone
+            .from_local_datetime(&local)
+        {
+            LocalResult::Single(instant) => vec![instant.to_utc()],
+            LocalResult::Ambiguous(first, last) => match self.ambiguous {
+                Ambiguous::First => vec![first.to_utc()],
+                Ambiguous::Last => vec![last.to_utc()],
+                Ambiguous::Both => vec![first.to_utc(), last.to_utc()],
+            },
+            LocalResult::None => match self.nonexistent {
+                Nonexistent::Skip => vec![],
+                Nonexistent::Shift => {
+                    let before = self
+                        .timezone
+                        .offset_from_utc_datetime(&(local - TimeDelta::days(1)))
+                        .fix();
+
+                    vec![
+                        (local - TimeDelta::seconds(i64::from(before.local_minus_utc()))).and_utc(),
+                    ]
+                }
+            },
+        }
+    }
+
+    /// How far the UTC offset moves within a day of `instant`.
+    fn drift(&self, instant: DateTime<Utc>) -> TimeDelta {
+        let offset = |instant: DateTime<Utc>| {
+            self.timezone
+                .offset_from_utc_datetime(&instant.naive_utc())
+                .fix()
+                .local_minus_utc()
+        };
+
+        let before = offset(instant - TimeDelta::days(1));
+        let after = offset(instant + TimeDelta::days(1));
+
+        TimeDelta::seconds(i64::from((after - before).abs()))
+    }
+}
+
+struct ScheduleVisitor;
+
+impl ScheduleVisitor {
+    const FIELDS: &[&str] = &[
+        "schedule",
+        "every",
+        "anchor",
+        "at",
+        "timezone",
+        "nonexistent",
+        "ambiguous",
+    ];
+}
+
+impl<'de> Visitor<'de> for ScheduleVisitor {
+    type Value = Option<Schedule>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "one of `schedule`, `every` or `at`")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        let mut schedules = Vec::new();
+        let mut anchor = None;
+        let mut zone: Option<Zone> = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "schedule" => schedules.push(Schedule::Cron(map.next_value()?, Zone::default())),
+                "every" => {
+                    let every: String = map.next_value()?;
+                    let period = duration::parse(&every)
+                        .ok()
+                        .and_then(|period| TimeDelta::from_std(period).ok())
+                        .filter(|period| *period > TimeDelta::zero())
+                        .ok_or(Error::custom(format!(
+                            "invalid interval \"{every}\", expected a positive duration such as 90s or 1h 30m"
+                        )))?;
+
+                    schedules.push(Schedule::Every(Interval {
+                        period,
+                        anchor: Anchor::Startup,
+                    }));
+                }
+                "anchor" => {
+                    let value: String = map.next_value()?;
+
+                    anchor = Some(
+                        value
+                            .parse()
+                            .map_err(Error::custom)?,
+                    );
+                }
+                "at" => {
+                    let value: String = map.next_value()?;
+                    let at = DateTime::parse_from_rfc3339(&value).map_err(|err| {
+                        Error::custom(format!(
+                            "invalid `at` date \"{value}\", expected RFC3339: {err}"
+                        ))
+                    })?;
+
+                    schedules.push(Schedule::At(at.to_utc()));
+                }
+                "timezone" => {
+                    let value: String = map.next_value()?;
+                    let timezone = value.parse().map_err(|_| {
+                        Error::custom(format!(
+                            "unknown timezone \"{value}\", expected an IANA name such as Europe/Berlin"
+                        ))
+                    })?;
+
+                    zone.get_or_insert_with(Zone::default)
+                        .timezone = timezone;
+                }
+                "nonexistent" => {
+                    zone.get_or_insert_with(Zone::default)
+                        .nonexistent = map.next_value()?;
+                }
+                "ambiguous" => {
+                    zone.get_or_insert_with(Zone::default)
+                        .ambiguous = map.next_value()?;
+                }
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+
+        let mut schedule = match schedules.len() {
+            0 if anchor.is_none() && zone.is_none() => return Ok(None),
+            0 => {
+                return Err(Error::custom(
+                    "missing schedule, expected one of `schedule`, `every` or `at`",
+                ))
+            }
+            1 => schedules.remove(0),
+            _ => {
+                return Err(Error::custom(
+                    "expected only one of `schedule`, `every` or `at`",
+                ))
+            }
+        };
+
+        if let Some(anchor) = anchor {
+            match &mut schedule {
+                Schedule::Every(interval) => interval.anchor = anchor,
+                _ => return Err(Error::custom("`anchor` only applies to `every` schedules")),
+            }
+        }
+
+        if let Some(zone) = zone {
+            match &mut schedule {
+                Schedule::Cron(_, cron_zone) => *cron_zone = zone,
+                _ => {
+                    return Err(Error::custom(
+                        "`timezone`, `nonexistent` and `ambiguous` only apply to cron schedules",
+                    ))
+                }
+            }
+        }
+
+        Ok(Some(schedule))
+    }
+}
+
+impl<'de> Deserialize<'de> for Schedule {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_struct("Schedule", ScheduleVisitor::FIELDS, ScheduleVisitor)?
+            .ok_or_else(|| {
+                D::Error::custom("missing schedule, expected one of `schedule`, `every` or `at`")
+            })
+    }
+}
+
+/// Deserializes a schedule that may be left out, for use with
+/// `deserialize_with`.
+pub fn optional<'de, D>(deserializer: D) -> Result<Option<Schedule>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserializer.deserialize_struct("Schedule", ScheduleVisitor::FIELDS, ScheduleVisitor)
+}
+
+/// A cron expression: `[second] minute hour day-of-month month day-of-week [year]`.
+///
+/// Besides the standard syntax the `L`, `W` and `#` modifiers and the
+/// `@yearly`, `@monthly`, `@weekly`, `@daily` and `@hourly` macros are accepted.
+#[derive(Debug, PartialEq)]
+pub struct Cron {
+    expression: String,
+    cron: Box<croner::Cron>,
+}
+
+impl Cron {
+    /// Returns the first run strictly after `after` with the expression
+    /// evaluated on the wall clock of `zone`.
+    pub fn next_after(&self, after: DateTime<Utc>, zone: &Zone) -> Option<DateTime<Utc>> {
+        // Around a DST transition a later wall-clock time can resolve to an
+        // earlier instant, so the search starts `drift` early and keeps going
+        // until no later wall-clock time can beat the best instant found.
+        // Occurrences fall on whole seconds, whatever fraction `after` has.
+        let drift = zone.drift(after);
+        let mut local = after
+            .with_timezone(&zone.timezone)
+            .naive_local()
+            .trunc_subsecs(0)
+            - drift;
+        let mut next: Option<(NaiveDateTime, DateTime<Utc>)> = None;
+
+        while let Some(candidate) = self.next_local(local) {
+            if let Some((found, instant)) = next {
+                if candidate > found + zone.drift(instant) {
+                    break;
+                }
+            }
+
+            for instant in zone.resolve(candidate) {
+                if instant > after && next.is_none_or(|(_, next)| instant < next) {
+                    next = Some((candidate, instant));
+                }
+            }
+
+            local = candidate;
+        }
+
+        next.map(|(_, instant)| instant)
+    }
+
+    /// Returns the last run at or before `until`, searching back the way
+    /// `next_after` searches forward.
+    pub fn last_until(&self, until: DateTime<Utc>, zone: &Zone) -> Option<DateTime<Utc>> {
+        let drift = zone.drift(until);
+        let mut local = until
+            .with_timezone(&zone.timezone)
+            .naive_local()
+            .trunc_subsecs(0)
+            + drift
+            + TimeDelta::seconds(1);
+        let mut last: Option<(NaiveDateTime, DateTime<Utc>)> = None;
+
+        while let Some(candidate) = self.previous_local(local) {
+            if let Some((found, instant)) = last {
+                if candidate < found - zone.drift(instant) {
+                    break;
+                }
+            }
+
+            for instant in zone.resolve(candidate) {
+                if instant <= until && last.is_none_or(|(_, last)| instant > last) {
+                    last = Some((candidate, instant));
+                }
+            }
+
+            local = candidate;
+        }
+
+        last.map(|(_, instant)| instant)
+    }
+
+    fn next_local(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.cron
+            .find_next_occurrence(&after.and_utc(), false)
+            .ok()
+            .map(|next| next.naive_utc())
+    }
+
+    fn previous_local(&self, before: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.cron
+            .find_previous_occurrence(&before.and_utc(), false)
+            .ok()
+            .map(|previous| previous.naive_utc())
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+impl FromStr for Cron {
+    type Err = croner::errors::CronError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cron = CronParser::builder()
+            .seconds(Seconds::Optional)
+            .year(Year::Optional)
+            .build()
+            .parse(s)?;
+
+        Ok(Cron {
+            expression: String::from(s),
+            cron: Box::new(cron),
+        })
+    }
+}
+
+struct CronVisitor;
+
+impl<'de> Visitor<'de> for CronVisitor {
+    type Value = Cron;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cron expression, e.g. \"*/5 * * * *\" or \"@daily\"")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        value
+            .parse()
+            .map_err(|err| Error::custom(format!("invalid cron expression \"{value}\": {err}")))
+    }
+}
+
+impl<'de> Deserialize<'de> for Cron {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(CronVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, min, sec)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_deserialize_cron() {
+        success_deserialize_cron("\"*/5 * * * *\"");
+        success_deserialize_cron("\"*/10 * * * * *\"");
+        success_deserialize_cron("\"0 0 0 1 1 * 2030\"");
+        success_deserialize_cron("\"0 0 L * *\"");
+        success_deserialize_cron("\"0 0 15W * *\"");
+        success_deserialize_cron("\"0 0 * * 5#2\"");
+        success_deserialize_cron("\"@daily\"");
+
+        failure_deserialize_cron("\"\"");
+        failure_deserialize_cron("\"* * *\"");
+        failure_deserialize_cron("\"61 * * * *\"");
+        failure_deserialize_cron("\"@fortnightly\"");
+        failure_deserialize_cron("10");
+    }
+
+    fn success_deserialize_cron(input: &str) {
+        let is_ok = serde_yml::from_str::<Cron>(input).is_ok();
+
+        assert!(is_ok, "{input}");
+    }
+
+    fn failure_deserialize_cron(input: &str) {
+        let is_err = serde_yml::from_str::<Cron>(input).is_err();
+
+        assert!(is_err, "{input}");
+    }
+
+    #[test]
+    fn test_deserialize_schedule() {
+        assert_eq!(
+            Schedule::Cron("@daily".parse().unwrap(), Zone::default()),
+            serde_yml::from_str("schedule: \"@daily\"").unwrap()
+        );
+        assert_eq!(
+            Schedule::Cron(
+                "30 2 * * *".parse().unwrap(),
+                Zone {
+                    timezone: Tz::Europe__Berlin,
+                    nonexistent: Nonexistent::Skip,
+                    ambiguous: Ambiguous::Both,
+                }
+            ),
+            serde_yml::from_str(
+                "schedule: \"30 2 * * *\"\ntimezone: Europe/Berlin\nnonexistent: skip\nambiguous: both"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            Schedule::Every(Interval {
+                period: TimeDelta::seconds(90),
+                anchor: Anchor::Startup,
+            }),
+            serde_yml::from_str("every: 90s").unwrap()
+        );
+        assert_eq!(
+            Schedule::Every(Interval {
+                period: TimeDelta::minutes(90),
+                anchor: Anchor::Epoch(DateTime::UNIX_EPOCH),
+            }),
+            serde_yml::from_str("every: 1h 30m\nanchor: epoch").unwrap()
+        );
+        assert_eq!(
+            Schedule::Every(Interval {
+                period: TimeDelta::hours(1),
+                anchor: Anchor::Epoch(utc(2026, 1, 1, 0, 30, 0)),
+            }),
+            serde_yml::from_str("every: 1h\nanchor: 2026-01-01T01:30:00+01:00").unwrap()
+        );
+        assert_eq!(
+            Schedule::At(utc(2026, 12, 1, 0, 0, 0)),
+            serde_yml::from_str("at: 2026-12-01T00:00:00Z").unwrap()
+        );
+
+        failure_deserialize_schedule("name: load_data", "missing schedule");
+        failure_deserialize_schedule("every: 0s", "invalid interval \"0s\"");
+        failure_deserialize_schedule("every: often", "invalid interval \"often\"");
+        failure_deserialize_schedule("every: 1m\nanchor: noon", "invalid anchor \"noon\"");
+        failure_deserialize_schedule("at: tomorrow", "invalid `at` date \"tomorrow\"");
+        failure_deserialize_schedule(
+            "every: 1m\nat: 2026-12-01T00:00:00Z",
+            "expected only one of",
+        );
+        failure_deserialize_schedule(
+            "schedule: \"@daily\"\nanchor: epoch",
+            "`anchor` only applies to `every` schedules",
+        );
+        failure_deserialize_schedule(
+            "schedule: \"@daily\"\ntimezone: Mars/Olympus",
+            "unknown timezone \"Mars/Olympus\"",
+        );
+        failure_deserialize_schedule(
+            "schedule: \"@daily\"\nambiguous: never",
+            "unknown variant `never`",
+        );
+        failure_deserialize_schedule(
+            "every: 1h\ntimezone: Europe/Berlin",
+            "only apply to cron schedules",
+        );
+    }
+
+    fn failure_deserialize_schedule(input: &str, expected: &str) {
+        let err = serde_yml::from_str::<Schedule>(input)
+            .err()
+            .unwrap();
+
+        assert!(
+            err.to_string()
+                .contains(expected),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_interval_next_after() {
+        let started_at = utc(2026, 1, 1, 10, 0, 7);
+
+        let interval = Interval {
+            period: TimeDelta::seconds(90),
+            anchor: Anchor::Startup,
+        };
+        assert_eq!(
+            Some(utc(2026, 1, 1, 10, 1, 37)),
+            interval.next_after(started_at, started_at)
+        );
+        assert_eq!(
+            Some(utc(2026, 1, 1, 10, 3, 7)),
+            interval.next_after(utc(2026, 1, 1, 10, 1, 37), started_at)
+        );
+
+        let interval = Interval {
+            period: TimeDelta::minutes(15),
+            anchor: Anchor::Epoch(DateTime::UNIX_EPOCH),
+        };
+        assert_eq!(
+            Some(utc(2026, 1, 1, 10, 15, 0)),
+            interval.next_after(started_at, started_at)
+        );
+        assert_eq!(
+            Some(utc(2026, 1, 1, 10, 30, 0)),
+            interval.next_after(utc(2026, 1, 1, 10, 15, 0), started_at)
+        );
+
+        let interval = Interval {
+            period: TimeDelta::hours(1),
+            anchor: Anchor::Epoch(utc(2030, 1, 1, 0, 30, 0)),
+        };
+        assert_eq!(
+            Some(utc(2026, 1, 1, 10, 30, 0)),
+            interval.next_after(started_at, started_at)
+        );
+    }
+
+    #[test]
+    fn test_at_next_after() {
+        let schedule = Schedule::At(utc(2026, 12, 1, 0, 0, 0));
+        let started_at = utc(2026, 1, 1, 0, 0, 0);
+
+        assert_eq!(
+            Some(utc(2026, 12, 1, 0, 0, 0)),
+            schedule.next_after(started_at, started_at)
+        );
+        assert_eq!(
+            None,
+            schedule.next_after(utc(2026, 12, 1, 0, 0, 0), started_at)
+        );
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let cron: Cron = "*/5 * * * *".parse().unwrap();
+        assert_eq!(
+            Some(utc(2026, 1, 1, 10, 5, 0)),
+            cron.next_after(utc(2026, 1, 1, 10, 0, 0), &Zone::default())
+        );
+
+        assert_eq!(
+            Some(utc(2026, 1, 1, 10, 5, 0)),
+            cron.next_after(
+                utc(2026, 1, 1, 10, 0, 0) + TimeDelta::milliseconds(437),
+                &Zone::default()
+            )
+        );
+
+        let cron: Cron = "30 * * * * *"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            Some(utc(2026, 1, 1, 10, 0, 30)),
+            cron.next_after(utc(2026, 1, 1, 10, 0, 0), &Zone::default())
+        );
+
+        let cron: Cron = "0 0 L 2 *".parse().unwrap();
+        assert_eq!(
+            Some(utc(2028, 2, 29, 0, 0, 0)),
+            cron.next_after(utc(2028, 1, 1, 0, 0, 0), &Zone::default())
+        );
+
+        let cron: Cron = "@daily".parse().unwrap();
+        assert_eq!(
+            Some(utc(2026, 1, 2, 0, 0, 0)),
+            cron.next_after(utc(2026, 1, 1, 0, 0, 0), &Zone::default())
+        );
+
+        let cron: Cron = "0 0 0 1 1 * 2025"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            None,
+            cron.next_after(utc(2026, 1, 1, 0, 0, 0), &Zone::default())
+        );
+    }
+
+    #[test]
+    fn test_cron_next_after_in_timezone() {
+        let zone = |nonexistent, ambiguous| Zone {
+            timezone: Tz::Europe__Berlin,
+            nonexistent,
+            ambiguous,
+        };
+
+        let cron: Cron = "0 9 * * *".parse().unwrap();
+        let summer = zone(Nonexistent::Shift, Ambiguous::First);
+        assert_eq!(
+            Some(utc(2026, 7, 1, 7, 0, 0)),
+            cron.next_after(utc(2026, 7, 1, 0, 0, 0), &summer)
+        );
+
+        // 2026-03-29 02:00 CET jumps to 03:00 CEST
+        let cron: Cron = "30 2 * * *".parse().unwrap();
+        let before_gap = utc(2026, 3, 28, 12, 0, 0);
+
+        let skip = zone(Nonexistent::Skip, Ambiguous::First);
+        assert_eq!(
+            Some(utc(2026, 3, 30, 0, 30, 0)),
+            cron.next_after(before_gap, &skip)
+        );
+
+        let shift = zone(Nonexistent::Shift, Ambiguous::First);
+        assert_eq!(
+            Some(utc(2026, 3, 29, 1, 30, 0)),
+            cron.next_after(before_gap, &shift)
+        );
+        assert_eq!(
+            Some(utc(2026, 3, 30, 0, 30, 0)),
+            cron.next_after(utc(2026, 3, 29, 1, 30, 0), &shift)
+        );
+
+        // 2026-10-25 03:00 CEST falls back to 02:00 CET
+        let before_fold = utc(2026, 10, 24, 12, 0, 0);
+
+        let first = zone(Nonexistent::Shift, Ambiguous::First);
+        assert_eq!(
+            Some(utc(2026, 10, 25, 0, 30, 0)),
+            cron.next_after(before_fold, &first)
+        );
+        assert_eq!(
+            Some(utc(2026, 10, 26, 1, 30, 0)),
+            cron.next_after(utc(2026, 10, 25, 0, 30, 0), &first)
+        );
+
+        let last = zone(Nonexistent::Shift, Ambiguous::Last);
+        assert_eq!(
+            Some(utc(2026, 10, 25, 1, 30, 0)),
+            cron.next_after(before_fold, &last)
+        );
+
+        let both = zone(Nonexistent::Shift, Ambiguous::Both);
+        assert_eq!(
+            Some(utc(2026, 10, 25, 0, 30, 0)),
+            cron.next_after(before_fold, &both)
+        );
+        assert_eq!(
+            Some(utc(2026, 10, 25, 1, 30, 0)),
+            cron.next_after(utc(2026, 10, 25, 0, 30, 0), &both)
+        );
+        assert_eq!(
+            Some(utc(2026, 10, 26, 1, 30, 0)),
+            cron.next_after(utc(2026, 10, 25, 1, 30, 0), &both)
+        );
+
+        // wildcard runs inside the repeated hour fire on both passes
+        let cron: Cron = "0,30 * * * *"
+            .parse()
+            .unwrap();
+        let mut runs = Vec::new();
+        let mut after = utc(2026, 10, 24, 23, 45, 0);
+        while runs.len() < 6 {
+            after = cron
+                .next_after(after, &both)
+                .unwrap();
+            runs.push(after);
+        }
+        assert_eq!(
+            vec![
+                utc(2026, 10, 25, 0, 0, 0),
+                utc(2026, 10, 25, 0, 30, 0),
+                utc(2026, 10, 25, 1, 0, 0),
+                utc(2026, 10, 25, 1, 30, 0),
+                utc(2026, 10, 25, 2, 0, 0),
+                utc(2026, 10, 25, 2, 30, 0),
+            ],
+            runs
+        );
+    }
+    #[test]
+    fn test_last_until() {
+        let started_at = utc(2026, 1, 1, 10, 0, 7);
+
+        let every = Schedule::Every(Interval {
+            period: TimeDelta::seconds(90),
+            anchor: Anchor::Startup,
+        });
+        assert_eq!(
+            Some(utc(2026, 1, 1, 10, 1, 37)),
+            every.last_until(utc(2026, 1, 1, 10, 1, 37), started_at)
+        );
+        assert_eq!(
+            Some(utc(2026, 1, 1, 10, 1, 37)),
+            every.last_until(utc(2026, 1, 1, 10, 3, 6), started_at)
+        );
+
+        let at = Schedule::At(utc(2026, 12, 1, 0, 0, 0));
+        assert_eq!(None, at.last_until(utc(2026, 11, 30, 0, 0, 0), started_at));
+        assert_eq!(
+            Some(utc(2026, 12, 1, 0, 0, 0)),
+            at.last_until(utc(2026, 12, 1, 0, 0, 0), started_at)
+        );
+
+        let every_second = Schedule::Cron("* * * * * *".parse().unwrap(), Zone::default());
+        assert_eq!(
+            Some(utc(2026, 1, 1, 10, 0, 0)),
+            every_second.last_until(
+                utc(2026, 1, 1, 10, 0, 0) + TimeDelta::milliseconds(437),
+                started_at
+            )
+        );
+
+        let cron: Cron = "0 0 L 2 *".parse().unwrap();
+        assert_eq!(
+            Some(utc(2028, 2, 29, 0, 0, 0)),
+            cron.last_until(utc(2029, 2, 27, 23, 59, 59), &Zone::default())
+        );
+
+        // 2026-10-25 03:00 CEST falls back to 02:00 CET
+        let cron: Cron = "30 2 * * *".parse().unwrap();
+        let after_fold = utc(2026, 10, 25, 12, 0, 0);
+        let zone = |ambiguous| Zone {
+            timezone: Tz::Europe__Berlin,
+            nonexistent: Nonexistent::Shift,
+            ambiguous,
+        };
+
+        assert_eq!(
+            Some(utc(2026, 10, 25, 0, 30, 0)),
+            cron.last_until(after_fold, &zone(Ambiguous::First))
+        );
+        assert_eq!(
+            Some(utc(2026, 10, 25, 1, 30, 0)),
+            cron.last_until(after_fold, &zone(Ambiguous::Both))
+        );
+        assert_eq!(
+            Some(utc(2026, 10, 25, 0, 30, 0)),
+            cron.last_until(utc(2026, 10, 25, 1, 29, 59), &zone(Ambiguous::Both))
+        );
+    }
+}
@@ -1,5 +1,257 @@
+use super::duration;
+use chrono::format::{Item, StrftimeItems};
+use chrono::TimeDelta;
+use chrono_tz::Tz;
+use std::fmt;
+use std::str::FromStr;
+
 #[derive(Debug, PartialEq)]
 pub enum Source {
     LastExecuteDate,
     ExecuteDate,
+    /// A value the task extracted from an earlier response, `state.<name>`.
+    State(String),
+}
+
+impl FromStr for Source {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "last_execute_time" => Ok(Source::LastExecuteDate),
+            "execute_time" => Ok(Source::ExecuteDate),
+            _ => match s.strip_prefix("state.") {
+                Some(name) if !name.is_empty() => Ok(Source::State(String::from(name))),
+                _ => Err(()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::LastExecuteDate => write!(f, "last_execute_time"),
+            Source::ExecuteDate => write!(f, "execute_time"),
+            Source::State(name) => write!(f, "state.{name}"),
+        }
+    }
+}
+
+/// How a date source is rendered, e.g. as Unix seconds a day earlier.
+#[derive(Debug, PartialEq)]
+pub struct DateFormat {
+    pub format: Format,
+    /// The zone the date is shown in, it does not move the instant.
+    pub timezone: Tz,
+    /// Added to the date before it is rendered.
+    pub offset: Offset,
+    /// Applied after the offset, in `timezone`.
+    pub truncate: Option<Truncate>,
+}
+
+impl Default for DateFormat {
+    fn default() -> Self {
+        Self {
+            format: Format::Rfc3339,
+            timezone: Tz::UTC,
+            offset: Offset::default(),
+            truncate: None,
+        }
+    }
+}
+
+/// A signed shift of a date. Days and weeks move the calendar date in the
+/// format's timezone and keep the wall-clock time across DST changes, the
+/// rest is an exact duration.
+#[derive(Debug, PartialEq, Default, Clone, Copy)]
+pub struct Offset {
+    pub days: i64,
+    pub time: TimeDelta,
+}
+
+/// Rounds a date down to the start of its unit.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Truncate {
+    /// Local midnight, or the first instant of the day if DST skips it.
+    Day,
+}
+
+impl FromStr for Truncate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Truncate::Day),
+            _ => Err(format!("invalid truncate \"{s}\", expected day")),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Format {
+    Rfc3339,
+    /// Seconds since the epoch, rendered as a number.
+    Unix,
+    /// Milliseconds since the epoch, rendered as a number.
+    UnixMs,
+    Strftime(String),
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rfc3339" => Ok(Format::Rfc3339),
+            "unix" => Ok(Format::Unix),
+            "unix_ms" => Ok(Format::UnixMs),
+            // A pattern without a single specifier is more likely a typo.
+            pattern
+                if pattern.contains('%')
+                    && !StrftimeItems::new(pattern).any(|item| item == Item::Error) =>
+            {
+                Ok(Format::Strftime(String::from(pattern)))
+            }
+            _ => Err(format!(
+                "invalid date format \"{s}\", expected rfc3339, unix, unix_ms or a strftime pattern such as %Y%m%d"
+            )),
+        }
+    }
+}
+
+/// Parses a signed duration such as `-1h`, `+30m` or `1d`.
+pub fn parse_offset(value: &str) -> Result<Offset, String> {
+    let (negative, duration) = match value.trim().strip_prefix('-') {
+        Some(duration) => (true, duration),
+        None => (
+            false,
+            value
+                .trim()
+                .trim_start_matches('+'),
+        ),
+    };
+
+    let too_large = || format!("offset \"{value}\" is too large");
+
+    // Days and weeks are kept apart from the rest, which humantime reads.
+    let mut days: i64 = 0;
+    let mut rest = Vec::new();
+
+    for (amount, unit) in units(duration.trim()).ok_or_else(|| {
+        duration::parse(duration.trim())
+            .err()
+            .unwrap_or_else(too_large)
+    })? {
+        let per_unit = match unit {
+            "d" | "day" | "days" => 1,
+            "w" | "week" | "weeks" => 7,
+            _ => {
+                rest.push(format!("{amount}{unit}"));
+                continue;
+            }
+        };
+
+        days = amount
+            .parse::<i64>()
+            .ok()
+            .and_then(|amount| amount.checked_mul(per_unit))
+            .and_then(|amount| days.checked_add(amount))
+            .ok_or_else(too_large)?;
+    }
+
+    let time = match rest.is_empty() {
+        true => TimeDelta::zero(),
+        false => TimeDelta::from_std(duration::parse(&rest.join(" "))?).map_err(|_| too_large())?,
+    };
+
+    Ok(match negative {
+        true => Offset {
+            days: -days,
+            time: -time,
+        },
+        false => Offset { days, time },
+    })
+}
+
+/// Splits a duration such as `1d 2h` or `1h30m` into amounts and units, or
+/// returns `None` when it is not made of them.
+fn units(duration: &str) -> Option<Vec<(&str, &str)>> {
+    let mut units = Vec::new();
+    let mut rest = duration;
+
+    while !rest.is_empty() {
+        let (amount, after) = leading(rest, |ch| ch.is_ascii_digit())?;
+        let (unit, after) = leading(after.trim_start(), char::is_alphabetic)?;
+
+        units.push((amount, unit));
+        rest = after.trim_start();
+    }
+
+    (!units.is_empty()).then_some(units)
+}
+
+/// Splits off the non-empty run of `text` that `part` accepts.
+fn leading(text: &str, part: fn(char) -> bool) -> Option<(&str, &str)> {
+    let end = text
+        .find(|ch: char| !part(ch))
+        .unwrap_or(text.len());
+
+    (end > 0).then(|| text.split_at(end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(Ok(Format::Rfc3339), "rfc3339".parse());
+        assert_eq!(Ok(Format::Unix), "unix".parse());
+        assert_eq!(Ok(Format::UnixMs), "unix_ms".parse());
+        assert_eq!(
+            Ok(Format::Strftime(String::from("%Y%m%d"))),
+            "%Y%m%d".parse()
+        );
+        assert!("unixms"
+            .parse::<Format>()
+            .unwrap_err()
+            .contains("invalid date format \"unixms\""));
+        assert!("%Y%Q"
+            .parse::<Format>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        let offset = |days, time| Ok(Offset { days, time });
+
+        assert_eq!(offset(0, TimeDelta::hours(-1)), parse_offset("-1h"));
+        assert_eq!(offset(0, TimeDelta::minutes(30)), parse_offset("+30m"));
+        assert_eq!(offset(1, TimeDelta::zero()), parse_offset("1d"));
+        assert_eq!(offset(0, TimeDelta::minutes(-90)), parse_offset("- 1h 30m"));
+        assert_eq!(
+            offset(-15, TimeDelta::hours(-2)),
+            parse_offset("-2w 1day 2h")
+        );
+        assert_eq!(
+            offset(0, TimeDelta::milliseconds(1500)),
+            parse_offset("1s500ms")
+        );
+        assert!(parse_offset("yesterday")
+            .unwrap_err()
+            .contains("invalid duration \"yesterday\""));
+        assert_eq!(offset(2, TimeDelta::hours(3)), parse_offset("2 days 3 h"));
+        assert!(parse_offset("1d 2x").is_err());
+        assert!(parse_offset("").is_err());
+    }
+
+    #[test]
+    fn test_parse_truncate() {
+        assert_eq!(Ok(Truncate::Day), "day".parse());
+        assert_eq!(
+            Err(String::from("invalid truncate \"week\", expected day")),
+            "week".parse::<Truncate>()
+        );
+    }
 }
@@ -0,0 +1,18 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+
+/// Where task run history is persisted.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Store {
+    Json { path: PathBuf },
+    Sqlite { path: PathBuf },
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Store::Json {
+            path: PathBuf::from("state.json"),
+        }
+    }
+}
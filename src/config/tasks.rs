@@ -1,8 +1,211 @@
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
+use std::time::Duration;
 
-use super::http;
+use super::{duration, extract, http, pipeline, retry, schedule};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
 pub enum Task {
-    Http(http::Task),
+    Http(Box<http::Task>),
+    Pipeline(Box<pipeline::Task>),
+}
+
+/// Settings shared by every task kind.
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct Options {
+    /// Left out by tasks that run after their `depends_on` instead.
+    #[serde(flatten, deserialize_with = "schedule::optional")]
+    pub schedule: Option<schedule::Schedule>,
+    /// Tasks whose runs trigger this one.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub trigger_rule: TriggerRule,
+    pub retry: Option<retry::Retry>,
+    #[serde(default)]
+    pub misfire: Misfire,
+    /// How late a run may start and still count as on time.
+    #[serde(
+        default = "Options::default_misfire_grace",
+        deserialize_with = "duration::deserialize"
+    )]
+    pub misfire_grace: Duration,
+    /// How long a request may take, from connecting to reading the whole
+    /// response, before the attempt fails.
+    #[serde(
+        default = "Options::default_timeout",
+        deserialize_with = "duration::deserialize"
+    )]
+    pub timeout: Duration,
+    #[serde(default)]
+    pub concurrency: Concurrency,
+    /// How many runs may wait behind the one in flight with `queue`.
+    #[serde(default = "Options::default_queue_limit")]
+    pub queue_limit: usize,
+}
+
+impl Options {
+    fn default_misfire_grace() -> Duration {
+        Duration::from_secs(60)
+    }
+
+    fn default_timeout() -> Duration {
+        Duration::from_secs(30)
+    }
+
+    fn default_queue_limit() -> usize {
+        10
+    }
+}
+
+/// What happens when a run falls due while the previous one is in flight.
+#[derive(Deserialize, Debug, PartialEq, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Concurrency {
+    /// Runs overlap.
+    #[default]
+    Allow,
+    /// The new run is skipped.
+    Forbid,
+    /// The run in flight is cancelled in favour of the new one.
+    Replace,
+    /// The new run waits for the previous one, up to `queue_limit` runs.
+    Queue,
+}
+
+/// Which completed upstream runs trigger a task with `depends_on`.
+#[derive(Deserialize, Debug, PartialEq, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerRule {
+    /// Every upstream task succeeded.
+    #[default]
+    AllSuccess,
+    /// Every upstream task finished, whatever the outcome.
+    AllDone,
+    /// Any upstream task succeeded.
+    AnySuccess,
+}
+
+/// What happens to runs that were due longer ago than the misfire grace,
+/// e.g. while the scheduler was down.
+#[derive(Deserialize, Debug, PartialEq, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Misfire {
+    /// Run every missed occurrence, oldest first.
+    All,
+    /// Run only the most recent missed occurrence.
+    Latest,
+    #[default]
+    Skip,
+}
+
+impl Task {
+    pub fn name(&self) -> &str {
+        match self {
+            Task::Http(task) => task.name(),
+            Task::Pipeline(task) => task.name(),
+        }
+    }
+
+    pub fn options(&self) -> &Options {
+        match self {
+            Task::Http(task) => task.options(),
+            Task::Pipeline(task) => task.options(),
+        }
+    }
+
+    /// Checks what deserializing the task's fields one by one cannot.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Task::Http(task) => {
+                let values = task.request().values();
+
+                if values
+                    .iter()
+                    .any(|value| !value.step_fields().is_empty())
+                {
+                    return Err(format!(
+                        "task `{}` uses a `step` value, which only pipeline steps can",
+                        task.name()
+                    ));
+                }
+
+                if let Some(field) = values
+                    .iter()
+                    .flat_map(|value| value.state_fields())
+                    .find(|field| {
+                        !task
+                            .extract()
+                            .contains_key(*field)
+                    })
+                {
+                    return Err(format!(
+                        "task `{}` reads `state.{field}`, which it does not extract",
+                        task.name()
+                    ));
+                }
+
+                Ok(())
+            }
+            Task::Pipeline(task) => {
+                if let Some(field) = task
+                    .steps()
+                    .iter()
+                    .flat_map(|step| step.request().values())
+                    .flat_map(|value| value.state_fields())
+                    .next()
+                {
+                    return Err(format!(
+                        "pipeline `{}` reads `state.{field}`, but pipelines keep no state",
+                        task.name()
+                    ));
+                }
+
+                task.validate()
+            }
+        }
+    }
+
+    /// The state a task starts from before anything was extracted.
+    pub fn state_defaults(&self) -> extract::Values {
+        match self {
+            Task::Http(task) => task
+                .extract()
+                .iter()
+                .filter_map(|(name, extract)| {
+                    extract
+                        .default
+                        .clone()
+                        .map(|default| (name.clone(), default))
+                })
+                .collect(),
+            Task::Pipeline(_) => extract::Values::new(),
+        }
+    }
+
+    /// Returns the first run of the task strictly after `after`, or `None`
+    /// when the task has nothing left to run.
+    pub fn next_run(
+        &self,
+        after: DateTime<Utc>,
+        started_at: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        self.options()
+            .schedule
+            .as_ref()?
+            .next_after(after, started_at)
+    }
+
+    /// Returns the last run of the task at or before `until`.
+    pub fn last_run_until(
+        &self,
+        until: DateTime<Utc>,
+        started_at: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        self.options()
+            .schedule
+            .as_ref()?
+            .last_until(until, started_at)
+    }
 }
@@ -0,0 +1,295 @@
+use super::source::Source;
+use chrono::format::{Item, StrftimeItems};
+use std::fmt;
+use std::str::FromStr;
+
+/// A string with `{{ value | filter }}` placeholders, e.g.
+/// `Bearer {{ state.token }}` or `/reports/{{ execute_time | date('%Y-%m-%d') }}`.
+#[derive(Debug, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Part {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Placeholder {
+    pub subject: Subject,
+    /// Applied in order.
+    pub filters: Vec<Filter>,
+}
+
+/// What a placeholder reads.
+#[derive(Debug, PartialEq)]
+pub enum Subject {
+    Source(Source),
+    /// `step.<step>.<field>`, a field extracted by an earlier pipeline step.
+    Step {
+        step: String,
+        field: String,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Filter {
+    /// Formats an RFC3339 date with a strftime format.
+    Date(String),
+    /// Used when the value is missing.
+    Default(String),
+    Upper,
+    Lower,
+    /// Percent-encodes the value, e.g. for a path segment.
+    UrlEncode,
+}
+
+impl fmt::Display for Subject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Subject::Source(source) => write!(f, "{source}"),
+            Subject::Step { step, field } => write!(f, "step.{step}.{field}"),
+        }
+    }
+}
+
+impl Template {
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    pub fn placeholders(&self) -> impl Iterator<Item = &Placeholder> {
+        self.parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Placeholder(placeholder) => Some(placeholder),
+                Part::Literal(_) => None,
+            })
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Literal(String::from(&rest[..start])));
+            }
+
+            let end = rest[start..]
+                .find("}}")
+                .ok_or_else(|| format!("unclosed `{{{{` in template \"{s}\""))?;
+
+            let placeholder = parse_placeholder(rest[start + 2..start + end].trim())
+                .map_err(|err| format!("{err} in template \"{s}\""))?;
+
+            parts.push(Part::Placeholder(placeholder));
+            rest = &rest[start + end + 2..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(Part::Literal(String::from(rest)));
+        }
+
+        Ok(Template { parts })
+    }
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Placeholder, String> {
+    let mut pipes = split_pipes(placeholder).into_iter();
+    let subject = pipes
+        .next()
+        .unwrap_or_default()
+        .trim();
+
+    let subject = match subject.strip_prefix("step.") {
+        Some(step_field) => match step_field.split_once('.') {
+            Some((step, field)) if !step.is_empty() && !field.is_empty() => Subject::Step {
+                step: String::from(step),
+                field: String::from(field),
+            },
+            _ => return Err(format!("invalid step value \"{subject}\"")),
+        },
+        None => Subject::Source(subject.parse().map_err(|_| {
+            format!(
+                "unknown value \"{subject}\", expected execute_time, last_execute_time, state.<name> or step.<step>.<field>"
+            )
+        })?),
+    };
+
+    let filters = pipes
+        .map(|filter| parse_filter(filter.trim()))
+        .collect::<Result<_, _>>()?;
+
+    Ok(Placeholder { subject, filters })
+}
+
+/// Splits on `|` outside of quoted filter arguments.
+fn split_pipes(s: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+
+    for (index, ch) in s.char_indices() {
+        match (quote, ch) {
+            (None, '\'' | '"') => quote = Some(ch),
+            (Some(open), ch) if ch == open => quote = None,
+            (None, '|') => {
+                pieces.push(&s[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    pieces.push(&s[start..]);
+    pieces
+}
+
+fn parse_filter(filter: &str) -> Result<Filter, String> {
+    let (name, argument) = match filter.split_once('(') {
+        Some((name, argument)) => {
+            let argument = argument
+                .strip_suffix(')')
+                .map(str::trim)
+                .and_then(unquote)
+                .ok_or_else(|| {
+                    format!("invalid filter `{filter}`, expected a quoted argument such as date('%Y-%m-%d')")
+                })?;
+
+            (name.trim(), Some(argument))
+        }
+        None => (filter, None),
+    };
+
+    match (name, argument) {
+        ("date", Some(format)) => {
+            if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                return Err(format!("invalid date format \"{format}\""));
+            }
+
+            Ok(Filter::Date(String::from(format)))
+        }
+        ("default", Some(default)) => Ok(Filter::Default(String::from(default))),
+        ("upper", None) => Ok(Filter::Upper),
+        ("lower", None) => Ok(Filter::Lower),
+        ("urlencode", None) => Ok(Filter::UrlEncode),
+        ("date" | "default", None) => Err(format!("filter `{name}` takes an argument")),
+        ("upper" | "lower" | "urlencode", Some(_)) => {
+            Err(format!("filter `{name}` takes no argument"))
+        }
+        (name, _) => Err(format!(
+            "unknown filter `{name}`, expected one of date, default, upper, lower or urlencode"
+        )),
+    }
+}
+
+fn unquote(argument: &str) -> Option<&str> {
+    ['\'', '"']
+        .into_iter()
+        .find_map(|quote| {
+            argument
+                .strip_prefix(quote)?
+                .strip_suffix(quote)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(text: &str) -> Part {
+        Part::Literal(String::from(text))
+    }
+
+    #[test]
+    fn test_parse_template() {
+        assert_eq!(
+            Template {
+                parts: vec![
+                    literal("Bearer "),
+                    Part::Placeholder(Placeholder {
+                        subject: Subject::Source(Source::State(String::from("token"))),
+                        filters: vec![],
+                    }),
+                ],
+            },
+            "Bearer {{ state.token }}"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(
+            Template {
+                parts: vec![
+                    literal("/reports/"),
+                    Part::Placeholder(Placeholder {
+                        subject: Subject::Source(Source::ExecuteDate),
+                        filters: vec![Filter::Date(String::from("%Y|%m"))],
+                    }),
+                    literal("/"),
+                    Part::Placeholder(Placeholder {
+                        subject: Subject::Step {
+                            step: String::from("login"),
+                            field: String::from("user"),
+                        },
+                        filters: vec![
+                            Filter::Default(String::from("anonymous")),
+                            Filter::Lower,
+                            Filter::UrlEncode,
+                        ],
+                    }),
+                ],
+            },
+            "/reports/{{execute_time|date(\"%Y|%m\")}}/{{ step.login.user | default('anonymous') | lower | urlencode }}"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(
+            Template {
+                parts: vec![literal("no placeholders }}")],
+            },
+            "no placeholders }}"
+                .parse()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_template_errors() {
+        let failure = |input: &str, expected: &str| {
+            let err = input
+                .parse::<Template>()
+                .err()
+                .unwrap();
+
+            assert!(err.contains(expected), "{err}");
+        };
+
+        failure(
+            "{{ execute_time",
+            "unclosed `{{` in template \"{{ execute_time\"",
+        );
+        failure("{{ now }}", "unknown value \"now\"");
+        failure("{{ step.login }}", "invalid step value \"step.login\"");
+        failure("{{ execute_time | trim }}", "unknown filter `trim`");
+        failure(
+            "{{ execute_time | date }}",
+            "filter `date` takes an argument",
+        );
+        failure(
+            "{{ execute_time | upper('x') }}",
+            "filter `upper` takes no argument",
+        );
+        failure("{{ execute_time | date(%Y) }}", "invalid filter `date(%Y)`");
+        failure(
+            "{{ execute_time | date('%Q') }}",
+            "invalid date format \"%Q\"",
+        );
+    }
+}
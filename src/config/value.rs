@@ -1,11 +1,12 @@
-use crate::config::source;
+use crate::config::{source, template};
 
-use std::collections::HashMap;
+use indexmap::IndexMap;
 
 #[derive(Debug, PartialEq)]
 pub enum Value {
     Array(Vec<Value>),
-    Object(HashMap<String, Value>),
+    /// Properties in the order they are written.
+    Object(IndexMap<String, Value>),
     String(String),
     Bool(bool),
     Float(f64),
@@ -13,6 +14,15 @@ pub enum Value {
     Null,
 
     Source(source::Source),
+    /// A date source with its `format`, `timezone`, `offset` or `truncate`.
+    Date(source::Source, source::DateFormat),
+    /// A string with `{{ }}` placeholders, rendered at send time.
+    Template(template::Template),
+    /// A field extracted from the response of an earlier pipeline step.
+    Step {
+        step: String,
+        field: String,
+    },
 }
 
 #[derive(Debug)]
@@ -24,7 +34,12 @@ pub enum ParseEntryError {
     InvalidProperties,
     InvalidSource,
     InvalidSourceValue(String),
+    InvalidStep,
+    InvalidDateFormat(String),
+    InvalidTemplate(String),
     InvalidTypeValue(String),
+    NotScalar,
+    UnknownTag(String),
 }
 use serde::de::Error;
 
@@ -44,13 +59,26 @@ impl ParseEntryError {
                 Error::custom("invalid 'properties' tag, shourld be map")
             }
             ParseEntryError::InvalidSource => Error::custom("invalid 'source' tag"),
-            ParseEntryError::InvalidSourceValue(source) => {
-                Error::unknown_variant(source.as_str(), &["execute_time", "last_execute_time"])
+            ParseEntryError::InvalidSourceValue(source) => Error::unknown_variant(
+                source.as_str(),
+                &["execute_time", "last_execute_time", "state.<name>"],
+            ),
+            ParseEntryError::InvalidStep => {
+                Error::custom("invalid 'step' or 'field' tag, should be strings")
+            }
+            ParseEntryError::InvalidDateFormat(reason) => Error::custom(reason),
+            ParseEntryError::InvalidTemplate(reason) => Error::custom(reason),
+            ParseEntryError::NotScalar => {
+                Error::custom("expected a string, number or boolean, or an entry with a `type`")
             }
+            ParseEntryError::UnknownTag(tag) => Error::custom(format!(
+                "unknown tag `{tag}`, expected !source or !template"
+            )),
             ParseEntryError::InvalidTypeValue(entry_type) => Error::unknown_variant(
                 entry_type.as_str(),
                 &[
                     "array", "object", "integer", "float", "string", "boolean", "null", "source",
+                    "step", "template",
                 ],
             ),
         }
@@ -63,21 +91,91 @@ impl Value {
     const ITEMS_TAG: &str = "items";
     const VALUE_TAG: &str = "value";
     const SOURCE_TAG: &str = "source";
+    const STEP_TAG: &str = "step";
+    const FIELD_TAG: &str = "field";
+    const FORMAT_TAG: &str = "format";
+    const TIMEZONE_TAG: &str = "timezone";
+    const OFFSET_TAG: &str = "offset";
+    const TRUNCATE_TAG: &str = "truncate";
+
+    /// Parses a value, either written plainly and typed by its YAML kind
+    /// or as a mapping with a `type`. A mapping with a `type` key is always
+    /// read as the latter, so an object with a `type` property has to be
+    /// written as `type: object`.
+    pub fn from_entry(entry: &serde_yml::Value) -> Result<Self, ParseEntryError> {
+        match entry {
+            serde_yml::Value::Mapping(entry) if Self::is_typed(entry) => {
+                Self::parse_entry_by_type(Self::get_type(entry)?, entry)
+            }
+            serde_yml::Value::Mapping(properties) => Self::parse_properties(properties),
+            serde_yml::Value::Sequence(items) => Self::parse_items(items),
+            serde_yml::Value::Tagged(tagged) => Self::from_tagged(tagged),
+            entry => Self::from_scalar(entry),
+        }
+    }
+
+    /// Parses a value that renders to a single string, such as a header.
+    pub fn from_basic_entry(entry: &serde_yml::Value) -> Result<Self, ParseEntryError> {
+        match entry {
+            serde_yml::Value::Mapping(entry) if Self::is_typed(entry) => {
+                Self::parse_basic_entry_by_type(Self::get_type(entry)?, entry)
+            }
+            serde_yml::Value::Mapping(_) | serde_yml::Value::Sequence(_) => {
+                Err(ParseEntryError::NotScalar)
+            }
+            serde_yml::Value::Tagged(tagged) => Self::from_tagged(tagged),
+            entry => Self::from_scalar(entry),
+        }
+    }
 
-    pub fn from_entry(entry: &serde_yml::Mapping) -> Result<Self, ParseEntryError> {
-        println!("{:?}", entry);
-        let entry_type = Self::get_type(&entry)?;
+    /// Parses `!source execute_time`, `!source {source: ..., format: ...}`
+    /// and `!template "..."`. The config is read with these already turned
+    /// into mappings and `!env` and `!file` resolved, see `yaml`.
+    fn from_tagged(tagged: &serde_yml::value::TaggedValue) -> Result<Self, ParseEntryError> {
+        let tag = &tagged.tag;
 
-        Self::parse_entry_by_type(entry_type, &entry)
+        let entry = |key: &str| {
+            serde_yml::Mapping::from_iter([(serde_yml::Value::from(key), tagged.value.clone())])
+        };
+
+        match &tagged.value {
+            serde_yml::Value::String(_) if *tag == Self::SOURCE_TAG => {
+                Self::get_source(&entry(Self::SOURCE_TAG))
+            }
+            serde_yml::Value::Mapping(options) if *tag == Self::SOURCE_TAG => {
+                Self::get_source(options)
+            }
+            serde_yml::Value::String(_) if *tag == "template" => {
+                Self::get_template(&entry(Self::VALUE_TAG))
+            }
+            _ if *tag == Self::SOURCE_TAG || *tag == "template" => {
+                Err(ParseEntryError::InvalidValue)
+            }
+            _ => Err(ParseEntryError::UnknownTag(tag.to_string())),
+        }
     }
 
-    pub fn from_basic_entry(entry: &serde_yml::Mapping) -> Result<Self, ParseEntryError> {
-        let entry_type = Self::get_type(&entry)?;
+    fn is_typed(entry: &serde_yml::Mapping) -> bool {
+        entry.contains_key(Self::TYPE_TAG)
+    }
 
-        Self::parse_basic_entry_by_type(entry_type, &entry)
+    fn from_scalar(entry: &serde_yml::Value) -> Result<Self, ParseEntryError> {
+        match entry {
+            serde_yml::Value::Null => Ok(Value::Null),
+            serde_yml::Value::Bool(value) => Ok(Value::Bool(*value)),
+            serde_yml::Value::Number(value) => match value.as_i64() {
+                Some(value) => Ok(Value::Integer(value)),
+                None => value
+                    .as_f64()
+                    .map(Value::Float)
+                    .ok_or(ParseEntryError::InvalidValue),
+            },
+            serde_yml::Value::String(value) => Ok(Value::String(value.clone())),
+            _ => Err(ParseEntryError::InvalidValue),
+        }
     }
 
-    fn get_type<'a>(entry: &'a serde_yml::Mapping) -> Result<&'a str, ParseEntryError> {
+    fn get_type(entry: &serde_yml::Mapping) -> Result<&str, ParseEntryError> {
         let type_tag = serde_yml::Value::String(String::from(Self::TYPE_TAG));
 
         let entry_type = entry
@@ -91,9 +189,7 @@ impl Value {
         Ok(entry_type)
     }
 
-    fn get_items<'a>(
-        entry: &'a serde_yml::Mapping,
-    ) -> Result<&'a Vec<serde_yml::Value>, ParseEntryError> {
+    fn get_items(entry: &serde_yml::Mapping) -> Result<&Vec<serde_yml::Value>, ParseEntryError> {
         let items_tag = serde_yml::Value::String(String::from(Self::ITEMS_TAG));
 
         let items = entry
@@ -107,9 +203,7 @@ impl Value {
         Ok(sequence)
     }
 
-    fn get_properties<'a>(
-        entry: &'a serde_yml::Mapping,
-    ) -> Result<&'a serde_yml::Mapping, ParseEntryError> {
+    fn get_properties(entry: &serde_yml::Mapping) -> Result<&serde_yml::Mapping, ParseEntryError> {
         let properties_tag = serde_yml::Value::String(String::from(Self::PROPERTIES_TAG));
 
         let properties_tag_value = entry
@@ -123,9 +217,7 @@ impl Value {
         Ok(mapping)
     }
 
-    fn get_value<'a>(
-        entry: &'a serde_yml::Mapping,
-    ) -> Result<&'a serde_yml::Value, ParseEntryError> {
+    fn get_value(entry: &serde_yml::Mapping) -> Result<&serde_yml::Value, ParseEntryError> {
         let value_tag = serde_yml::Value::String(String::from(Self::VALUE_TAG));
 
         let value = entry
@@ -178,26 +270,28 @@ impl Value {
     fn get_string(entry: &serde_yml::Mapping) -> Result<Self, ParseEntryError> {
         let value = Self::get_value(entry)?;
 
+        // Forces other scalars, e.g. a zip code, to be sent as text.
         match value {
             serde_yml::Value::String(value) => Ok(Value::String(value.clone())),
+            serde_yml::Value::Number(value) => Ok(Value::String(value.to_string())),
+            serde_yml::Value::Bool(value) => Ok(Value::String(value.to_string())),
             _ => Err(ParseEntryError::InvalidValue),
         }
     }
 
     fn get_object(entry: &serde_yml::Mapping) -> Result<Self, ParseEntryError> {
-        let properties = Self::get_properties(entry)?;
-        let mut object: HashMap<String, Value> = HashMap::with_capacity(properties.len());
+        Self::parse_properties(Self::get_properties(entry)?)
+    }
+
+    fn parse_properties(properties: &serde_yml::Mapping) -> Result<Self, ParseEntryError> {
+        let mut object: IndexMap<String, Value> = IndexMap::with_capacity(properties.len());
 
         for (key, value) in properties {
             let key = key
                 .as_str()
                 .ok_or(ParseEntryError::InvalidProperties)?;
 
-            let entry = value
-                .as_mapping()
-                .ok_or(ParseEntryError::InvalidProperties)?;
-
-            let json_value = Self::from_entry(entry)?;
+            let json_value = Self::from_entry(value)?;
 
             _ = object.insert(String::from(key), json_value);
         }
@@ -206,20 +300,15 @@ impl Value {
     }
 
     fn get_array(entry: &serde_yml::Mapping) -> Result<Self, ParseEntryError> {
-        let items = Self::get_items(entry)?;
-        let mut array = Vec::with_capacity(items.len());
-
-        for value in items {
-            let entry = value
-                .as_mapping()
-                .ok_or(ParseEntryError::InvalidItems)?;
-
-            let json_value = Self::from_entry(entry)?;
-
-            array.push(json_value);
-        }
+        Self::parse_items(Self::get_items(entry)?)
+    }
 
-        Ok(Value::Array(array))
+    fn parse_items(items: &[serde_yml::Value]) -> Result<Self, ParseEntryError> {
+        items
+            .iter()
+            .map(Self::from_entry)
+            .collect::<Result<_, _>>()
+            .map(Value::Array)
     }
 
     fn get_source(entry: &serde_yml::Mapping) -> Result<Self, ParseEntryError> {
@@ -233,13 +322,159 @@ impl Value {
             .as_str()
             .ok_or(ParseEntryError::InvalidSource)?;
 
-        match source {
-            "last_execute_time" => Ok(Value::Source(
-                crate::config::source::Source::LastExecuteDate,
-            )),
-            "execute_time" => Ok(Value::Source(crate::config::source::Source::ExecuteDate)),
-            _ => Err(ParseEntryError::InvalidSourceValue(String::from(source))),
+        let source: source::Source = source
+            .parse()
+            .map_err(|_| ParseEntryError::InvalidSourceValue(String::from(source)))?;
+
+        let text = |name: &str| -> Result<Option<&str>, ParseEntryError> {
+            match entry.get(serde_yml::Value::String(String::from(name))) {
+                Some(value) => value
+                    .as_str()
+                    .map(Some)
+                    .ok_or_else(|| {
+                        ParseEntryError::InvalidDateFormat(format!("`{name}` should be a string"))
+                    }),
+                None => Ok(None),
+            }
+        };
+
+        let format = text(Self::FORMAT_TAG)?;
+        let timezone = text(Self::TIMEZONE_TAG)?;
+        let offset = text(Self::OFFSET_TAG)?;
+        let truncate = text(Self::TRUNCATE_TAG)?;
+
+        if format.is_none() && timezone.is_none() && offset.is_none() && truncate.is_none() {
+            return Ok(Value::Source(source));
+        }
+
+        if let source::Source::State(_) = source {
+            return Err(ParseEntryError::InvalidDateFormat(String::from(
+                "`format`, `timezone`, `offset` and `truncate` only apply to execute_time and last_execute_time",
+            )));
         }
+
+        let mut date_format = source::DateFormat::default();
+
+        if let Some(format) = format {
+            date_format.format = format
+                .parse()
+                .map_err(ParseEntryError::InvalidDateFormat)?;
+        }
+
+        if let Some(timezone) = timezone {
+            date_format.timezone = timezone
+                .parse()
+                .map_err(|_| {
+                    ParseEntryError::InvalidDateFormat(format!(
+                    "unknown timezone \"{timezone}\", expected an IANA name such as Europe/Berlin"
+                ))
+                })?;
+        }
+
+        if let Some(offset) = offset {
+            date_format.offset =
+                source::parse_offset(offset).map_err(ParseEntryError::InvalidDateFormat)?;
+        }
+
+        if let Some(truncate) = truncate {
+            date_format.truncate = Some(
+                truncate
+                    .parse()
+                    .map_err(ParseEntryError::InvalidDateFormat)?,
+            );
+        }
+
+        Ok(Value::Date(source, date_format))
+    }
+
+    fn get_template(entry: &serde_yml::Mapping) -> Result<Self, ParseEntryError> {
+        let value = Self::get_value(entry)?
+            .as_str()
+            .ok_or(ParseEntryError::InvalidValue)?;
+
+        value
+            .parse()
+            .map(Value::Template)
+            .map_err(ParseEntryError::InvalidTemplate)
+    }
+
+    fn get_step(entry: &serde_yml::Mapping) -> Result<Self, ParseEntryError> {
+        let tag = |name: &'static str| {
+            entry
+                .get(serde_yml::Value::String(String::from(name)))
+                .ok_or(ParseEntryError::MissingField(name))?
+                .as_str()
+                .map(String::from)
+                .ok_or(ParseEntryError::InvalidStep)
+        };
+
+        Ok(Value::Step {
+            step: tag(Self::STEP_TAG)?,
+            field: tag(Self::FIELD_TAG)?,
+        })
+    }
+
+    /// Returns this value and every value nested in it.
+    fn nodes(&self) -> Vec<&Value> {
+        let mut nodes = vec![self];
+
+        match self {
+            Value::Array(items) => nodes.extend(
+                items
+                    .iter()
+                    .flat_map(Value::nodes),
+            ),
+            Value::Object(properties) => nodes.extend(
+                properties
+                    .values()
+                    .flat_map(Value::nodes),
+            ),
+            _ => {}
+        }
+
+        nodes
+    }
+
+    /// Returns the `(step, field)` pairs of every step value in here,
+    /// including template placeholders.
+    pub fn step_fields(&self) -> Vec<(&str, &str)> {
+        self.nodes()
+            .into_iter()
+            .flat_map(|node| match node {
+                Value::Step { step, field } => vec![(step.as_str(), field.as_str())],
+                Value::Template(template) => template
+                    .placeholders()
+                    .filter_map(|placeholder| match &placeholder.subject {
+                        template::Subject::Step { step, field } => {
+                            Some((step.as_str(), field.as_str()))
+                        }
+                        template::Subject::Source(_) => None,
+                    })
+                    .collect(),
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    /// Returns the names of every `state.<name>` source in here, including
+    /// template placeholders.
+    pub fn state_fields(&self) -> Vec<&str> {
+        self.nodes()
+            .into_iter()
+            .flat_map(|node| match node {
+                Value::Source(source::Source::State(name)) => vec![name.as_str()],
+                Value::Template(template) => template
+                    .placeholders()
+                    .filter_map(|placeholder| match &placeholder.subject {
+                        template::Subject::Source(source::Source::State(name)) => {
+                            Some(name.as_str())
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+                _ => vec![],
+            })
+            .collect()
     }
 
     fn parse_entry_by_type(
@@ -250,6 +485,8 @@ impl Value {
             "object" => Self::get_object(entry),
             "array" => Self::get_array(entry),
             "source" => Self::get_source(entry),
+            "step" => Self::get_step(entry),
+            "template" => Self::get_template(entry),
             "integer" => Self::get_integer(entry),
             "float" => Self::get_float(entry),
             "string" => Self::get_string(entry),
@@ -265,6 +502,8 @@ impl Value {
     ) -> Result<Self, ParseEntryError> {
         match entry_type {
             "source" => Self::get_source(entry),
+            "step" => Self::get_step(entry),
+            "template" => Self::get_template(entry),
             "integer" => Self::get_integer(entry),
             "float" => Self::get_float(entry),
             "string" => Self::get_string(entry),
@@ -272,3 +511,156 @@ impl Value {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(input: &str) -> Result<Value, ParseEntryError> {
+        Value::from_entry(&serde_yml::from_str(input).unwrap())
+    }
+
+    fn basic_entry(input: &str) -> Result<Value, ParseEntryError> {
+        Value::from_basic_entry(&serde_yml::from_str(input).unwrap())
+    }
+
+    #[test]
+    fn test_from_entry_plain() {
+        assert_eq!(
+            Value::Object(IndexMap::from_iter([
+                (String::from("name"), Value::String(String::from("hello"))),
+                (String::from("count"), Value::Integer(100)),
+                (String::from("ratio"), Value::Float(0.5)),
+                (String::from("done"), Value::Bool(false)),
+                (String::from("note"), Value::Null),
+                (
+                    String::from("tags"),
+                    Value::Array(vec![
+                        Value::String(String::from("a")),
+                        Value::Source(source::Source::ExecuteDate),
+                    ])
+                ),
+                (String::from("zip"), Value::String(String::from("01234"))),
+            ])),
+            entry(
+                "
+                name: hello
+                count: 100
+                ratio: 0.5
+                done: false
+                note: null
+                tags:
+                  - a
+                  - type: source
+                    source: execute_time
+                zip:
+                  type: string
+                  value: \"01234\""
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_from_entry_tagged() {
+        assert_eq!(
+            Value::Array(vec![
+                Value::Object(IndexMap::from_iter([(
+                    String::from("id"),
+                    Value::Integer(1)
+                )])),
+                Value::String(String::from("42")),
+                Value::Float(1.0),
+            ]),
+            entry(
+                "
+                type: array
+                items:
+                  - type: object
+                    properties:
+                      id: 1
+                  - type: string
+                    value: 42
+                  - type: float
+                    value: 1"
+            )
+            .unwrap()
+        );
+
+        // A `type` key always marks the tagged form.
+        assert!(matches!(
+            entry("{type: order, id: 1}"),
+            Err(ParseEntryError::InvalidTypeValue(entry_type)) if entry_type == "order"
+        ));
+        assert_eq!(
+            Value::Object(IndexMap::from_iter([(
+                String::from("type"),
+                Value::String(String::from("order"))
+            )])),
+            entry("{type: object, properties: {type: order}}").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_from_entry_tags() {
+        let value = entry(
+            "
+            since: !source last_execute_time
+            day: !source {source: execute_time, format: \"%Y%m%d\"}
+            auth: !template \"Bearer {{ state.token }}\"",
+        )
+        .unwrap();
+
+        let Value::Object(properties) = value else {
+            panic!("{value:?}");
+        };
+
+        assert_eq!(
+            Value::Source(source::Source::LastExecuteDate),
+            properties["since"]
+        );
+        assert!(matches!(
+            &properties["day"],
+            Value::Date(source::Source::ExecuteDate, format)
+                if format.format == source::Format::Strftime(String::from("%Y%m%d"))
+        ));
+        assert_eq!(vec!["token"], properties["auth"].state_fields());
+        assert!(matches!(
+            basic_entry("!source state.cursor"),
+            Ok(Value::Source(source::Source::State(name))) if name == "cursor"
+        ));
+        assert!(matches!(
+            entry("!env TOKEN"),
+            Err(ParseEntryError::UnknownTag(tag)) if tag == "!env"
+        ));
+        assert!(matches!(
+            entry("!template [a]"),
+            Err(ParseEntryError::InvalidValue)
+        ));
+    }
+
+    #[test]
+    fn test_from_basic_entry() {
+        assert_eq!(
+            Value::String(String::from("My Custom Key")),
+            basic_entry("My Custom Key").unwrap()
+        );
+        assert_eq!(Value::Integer(3), basic_entry("3").unwrap());
+        assert_eq!(
+            Value::Source(source::Source::LastExecuteDate),
+            basic_entry("{type: source, source: last_execute_time}").unwrap()
+        );
+        assert!(matches!(
+            basic_entry("[a, b]"),
+            Err(ParseEntryError::NotScalar)
+        ));
+        assert!(matches!(
+            basic_entry("{name: a}"),
+            Err(ParseEntryError::NotScalar)
+        ));
+        assert!(matches!(
+            basic_entry("{type: object, properties: {}}"),
+            Err(ParseEntryError::InvalidTypeValue(_))
+        ));
+    }
+}
@@ -0,0 +1,219 @@
+use super::http::Reply;
+use crate::config::assertion::{Assertion, Check};
+use std::time::Duration;
+
+/// Whether any of `assertions` reads the response as JSON.
+pub fn reads_json(assertions: &[Assertion]) -> bool {
+    assertions
+        .iter()
+        .any(|assertion| matches!(assertion, Assertion::Json { .. }))
+}
+
+/// Checks a reply against every assertion and explains all that failed.
+pub fn check(
+    assertions: &[Assertion],
+    reply: &Reply,
+    response: Option<&serde_json::Value>,
+) -> Result<(), String> {
+    let failures: Vec<String> = assertions
+        .iter()
+        .filter_map(|assertion| failure(assertion, reply, response))
+        .collect();
+
+    match failures.len() {
+        0 => Ok(()),
+        1 => Err(format!("assertion failed: {}", failures[0])),
+        _ => Err(format!("assertions failed: {}", failures.join("; "))),
+    }
+}
+
+fn failure(
+    assertion: &Assertion,
+    reply: &Reply,
+    response: Option<&serde_json::Value>,
+) -> Option<String> {
+    match assertion {
+        Assertion::Json { path, check } => {
+            let value = response.and_then(|response| path.find(response));
+
+            check_value(&path.to_string(), value, check, false)
+        }
+        Assertion::Header { name, check } => {
+            let value = reply
+                .headers
+                .get(name)
+                .map(|value| {
+                    serde_json::Value::String(
+                        String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                    )
+                });
+
+            check_value(&format!("header {name}"), value, check, true)
+        }
+        Assertion::BodyContains(text) => {
+            let contains = text.is_empty()
+                || reply
+                    .body
+                    .windows(text.len())
+                    .any(|window| window == text.as_bytes());
+
+            (!contains).then(|| format!("body does not contain \"{text}\""))
+        }
+        Assertion::MaxLatency(max) => (reply.latency > *max).then(|| {
+            let latency = Duration::from_millis(reply.latency.as_millis() as u64);
+
+            format!(
+                "response took {}, expected at most {}",
+                humantime::format_duration(latency),
+                humantime::format_duration(*max)
+            )
+        }),
+    }
+}
+
+/// Checks a JSON value, or a header's text when `header` is set, which is
+/// then also read as the number or other value it spells.
+fn check_value(
+    subject: &str,
+    value: Option<serde_json::Value>,
+    check: &Check,
+    header: bool,
+) -> Option<String> {
+    let expected = match check {
+        Check::Exists(true) => {
+            return value
+                .is_none()
+                .then(|| format!("{subject} is missing"))
+        }
+        Check::Exists(false) => {
+            return value.map(|value| format!("{subject} is {value}, expected it to be missing"))
+        }
+        Check::Equals(expected) => format!("{expected}"),
+        Check::Matches(pattern) => format!("to match /{}/", pattern.0.as_str()),
+        Check::Compare(comparison, bound) => format!("a number {comparison} {bound}"),
+    };
+
+    let Some(value) = value else {
+        return Some(format!("{subject} is missing, expected {expected}"));
+    };
+
+    let text = match &value {
+        serde_json::Value::String(text) => text.clone(),
+        value => value.to_string(),
+    };
+
+    let holds = match check {
+        Check::Exists(_) => true,
+        // Headers are text, so `200` also equals a header of "200", while
+        // JSON values keep their type.
+        Check::Equals(expected) => {
+            value == *expected
+                || (header
+                    && serde_json::from_str::<serde_json::Value>(&text).ok()
+                        == Some(expected.clone()))
+        }
+        Check::Matches(pattern) => pattern.0.is_match(&text),
+        Check::Compare(comparison, bound) => value
+            .as_f64()
+            .or_else(|| {
+                header
+                    .then(|| text.parse().ok())
+                    .flatten()
+            })
+            .is_some_and(|number| comparison.holds(number, *bound)),
+    };
+
+    (!holds).then(|| format!("{subject} is {value}, expected {expected}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use serde_json::json;
+
+    fn reply() -> Reply {
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", HeaderValue::from_static("application/json"));
+        headers.insert("X-Total", HeaderValue::from_static("42"));
+
+        Reply {
+            url: "http://localhost:3030/load"
+                .parse()
+                .unwrap(),
+            status: 200,
+            headers,
+            body: br#"{"status": "error", "count": 0}"#.to_vec(),
+            latency: Duration::from_millis(1250),
+        }
+    }
+
+    fn outcome(assertions: &str) -> Result<(), String> {
+        let assertions: Vec<Assertion> = serde_yml::from_str(assertions).unwrap();
+
+        check(
+            &assertions,
+            &reply(),
+            Some(&json!({"status": "error", "count": 0, "id": "a1", "total": "42"})),
+        )
+    }
+
+    #[test]
+    fn test_check_passes() {
+        assert_eq!(
+            Ok(()),
+            outcome(
+                "
+                - {json: $.status, equals: error}
+                - {json: $.id, matches: \"^[a-z][0-9]$\"}
+                - {json: $.count, at_most: 0}
+                - {json: $.missing, exists: false}
+                - {header: X-Total, equals: 42}
+                - {header: X-Total, greater_than: 41}
+                - {header: Content-Type, matches: json}
+                - body_contains: '\"count\": 0'
+                - max_latency: 2s"
+            )
+        );
+    }
+
+    #[test]
+    fn test_check_explains_failures() {
+        assert_eq!(
+            Err(String::from(
+                "assertion failed: $.status is \"error\", expected \"ok\""
+            )),
+            outcome("[{json: $.status, equals: ok}]")
+        );
+        assert_eq!(
+            Err(String::from(
+                "assertions failed: $.count is 0, expected a number greater than 0; \
+                 $.items is missing; \
+                 header X-Request-Id is missing, expected to match /^[0-9a-f]+$/; \
+                 body does not contain \"done\"; \
+                 response took 1s 250ms, expected at most 1s"
+            )),
+            outcome(
+                "
+                - {json: $.count, greater_than: 0}
+                - json: $.items
+                - {header: X-Request-Id, matches: \"^[0-9a-f]+$\"}
+                - body_contains: done
+                - max_latency: 1s"
+            )
+        );
+        assert_eq!(
+            Err(String::from(
+                "assertion failed: $.id is \"a1\", expected a number at least 1"
+            )),
+            outcome("[{json: $.id, at_least: 1}]")
+        );
+        assert_eq!(
+            Err(String::from(
+                "assertions failed: $.total is \"42\", expected 42; \
+                 $.total is \"42\", expected a number greater than 41"
+            )),
+            outcome("[{json: $.total, equals: 42}, {json: $.total, greater_than: 41}]")
+        );
+    }
+}
@@ -0,0 +1,22 @@
+use super::Run;
+use crate::state;
+use chrono::{TimeZone, Utc};
+use serde::de::DeserializeOwned;
+
+pub fn task<T: DeserializeOwned>(yaml: &str) -> T {
+    serde_yml::from_str(yaml).unwrap()
+}
+
+/// The run due at 10:05 on 2026-01-01, after one at 10:00.
+pub fn run() -> Run {
+    Run {
+        scheduled_at: Utc
+            .with_ymd_and_hms(2026, 1, 1, 10, 5, 0)
+            .unwrap(),
+        last_scheduled_at: Some(
+            Utc.with_ymd_and_hms(2026, 1, 1, 10, 0, 0)
+                .unwrap(),
+        ),
+        state: state::Values::new(),
+    }
+}
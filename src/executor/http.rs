@@ -0,0 +1,2088 @@
+use super::assertion;
+use super::pagination::Pager;
+use super::render::{Context, COMPONENT};
+use super::retry::{self, Backoff};
+use super::xml;
+use super::{Attempt, Outcome, Page, Report, Run};
+use crate::config::extract::Extract;
+use crate::config::http::{Body, Endpoint, Method, Query, Request, Task};
+use crate::config::pagination::Pagination;
+use crate::config::retry::Retry;
+use crate::config::value::Value;
+use crate::state;
+use chrono::Utc;
+use percent_encoding::utf8_percent_encode;
+use reqwest::{StatusCode, Url};
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Default, Clone)]
+pub struct HttpExecutor {
+    client: reqwest::Client,
+}
+
+/// What came back from the attempt that succeeded.
+pub struct Reply {
+    pub url: Url,
+    pub status: u16,
+    pub headers: reqwest::header::HeaderMap,
+    pub body: Vec<u8>,
+    /// From sending the request to reading the whole body.
+    pub latency: Duration,
+}
+
+/// Where an attempt is sent.
+pub enum Target {
+    /// The request's own url.
+    Request,
+    /// The request's url with these query parameters set, e.g. a page number.
+    Query(Vec<(String, String)>),
+    /// A url taken from an earlier response.
+    Url(Url),
+}
+
+/// Why an attempt failed and whether another one may help.
+struct Failure {
+    reason: String,
+    status: Option<u16>,
+    retryable: bool,
+    retry_after: Option<Duration>,
+}
+
+impl Failure {
+    /// A failure no retry can fix, e.g. an invalid rendered url.
+    fn fatal(reason: String) -> Self {
+        Self {
+            reason,
+            status: None,
+            retryable: false,
+            retry_after: None,
+        }
+    }
+}
+
+impl HttpExecutor {
+    pub async fn execute(&self, task: &Task, run: &Run) -> Report {
+        let mut attempts = Vec::new();
+        let mut pages = Vec::new();
+        let context = Context::new(run);
+
+        let result = match task.pagination() {
+            Some(pagination) => {
+                self.paginate(task, pagination, &context, &mut attempts, &mut pages)
+                    .await
+            }
+            None => self
+                .send(
+                    task.request(),
+                    &context,
+                    &Target::Request,
+                    task.options().retry.as_ref(),
+                    task.options().timeout,
+                    &mut attempts,
+                )
+                .await
+                .and_then(|reply| accept(task, &reply, false))
+                .map(|(_, state)| state),
+        };
+
+        match result {
+            Ok(state) => Report {
+                outcome: Outcome::Success,
+                attempts,
+                pages,
+                state,
+            },
+            Err(reason) => Report {
+                outcome: Outcome::Failure(reason),
+                attempts,
+                pages,
+                state: state::Values::new(),
+            },
+        }
+    }
+
+    /// Requests page after page, each with its own retries. Values are
+    /// extracted from every page, later pages overriding earlier ones.
+    async fn paginate(
+        &self,
+        task: &Task,
+        pagination: &Pagination,
+        context: &Context<'_>,
+        attempts: &mut Vec<Attempt>,
+        pages: &mut Vec<Page>,
+    ) -> Result<state::Values, String> {
+        let mut pager = Pager::new(pagination);
+        let mut target = pager.first();
+        let mut state = state::Values::new();
+
+        loop {
+            let page = pages.len() + 1;
+            let reply = self
+                .send(
+                    task.request(),
+                    context,
+                    &target,
+                    task.options().retry.as_ref(),
+                    task.options().timeout,
+                    attempts,
+                )
+                .await
+                .map_err(|reason| format!("page {page} failed: {reason}"))?;
+            let (response, values) = accept(task, &reply, pager.reads_json())
+                .map_err(|reason| format!("page {page} failed: {reason}"))?;
+
+            state.extend(values);
+
+            let items = pager.items(response.as_ref())?;
+
+            pages.push(Page {
+                url: reply.url.to_string(),
+                status: reply.status,
+                items,
+            });
+
+            let Some(next) = pager.next(&reply, response.as_ref(), items)? else {
+                break;
+            };
+
+            if pages.len() >= pagination.max_pages.get() {
+                tracing::warn!(
+                    task = task.name(),
+                    max_pages = pagination.max_pages,
+                    "stopped paginating at `max_pages`"
+                );
+                break;
+            }
+
+            target = next;
+        }
+
+        tracing::debug!(
+            task = task.name(),
+            pages = pages.len(),
+            items = pages
+                .iter()
+                .filter_map(|page| page.items)
+                .sum::<usize>(),
+            "paginated"
+        );
+
+        Ok(state)
+    }
+
+    /// Sends `request` until it succeeds or `retry` gives up, recording every
+    /// attempt. Returns the reason of the last failure.
+    pub async fn send(
+        &self,
+        request: &Request,
+        context: &Context<'_>,
+        target: &Target,
+        retry: Option<&Retry>,
+        timeout: Duration,
+        attempts: &mut Vec<Attempt>,
+    ) -> Result<Reply, String> {
+        let mut backoff = Backoff::new(retry);
+
+        loop {
+            let started_at = Utc::now();
+            let result = self
+                .attempt(request, context, target, timeout, &backoff)
+                .await;
+            let finished_at = Utc::now();
+
+            let failure = match result {
+                Ok(reply) => {
+                    attempts.push(Attempt {
+                        started_at,
+                        finished_at,
+                        status: Some(reply.status),
+                        outcome: Outcome::Success,
+                        step: None,
+                    });
+
+                    return Ok(reply);
+                }
+                Err(failure) => failure,
+            };
+
+            attempts.push(Attempt {
+                started_at,
+                finished_at,
+                status: failure.status,
+                outcome: Outcome::Failure(failure.reason.clone()),
+                step: None,
+            });
+
+            let delay = if failure.retryable {
+                backoff.next_delay(failure.retry_after)
+            } else {
+                None
+            };
+
+            match delay {
+                Some(delay) => {
+                    tracing::debug!(reason = failure.reason, ?delay, "retrying");
+                    tokio::time::sleep(delay).await;
+                }
+                None => return Err(failure.reason),
+            }
+        }
+    }
+
+    async fn attempt(
+        &self,
+        request: &Request,
+        context: &Context<'_>,
+        target: &Target,
+        timeout: Duration,
+        backoff: &Backoff<'_>,
+    ) -> Result<Reply, Failure> {
+        let url = match (target, request.url()) {
+            // A url from a response already carries its query.
+            (Target::Url(url), _) => url.clone(),
+            (target, Endpoint::Fixed(url)) => with_query(
+                with_parameters(url.clone(), request.query(), context),
+                target,
+            ),
+            (target, Endpoint::Value(value)) => {
+                let url = context
+                    .text(value)
+                    .ok_or_else(|| no_value("url", value, context))?;
+
+                let url = Url::parse(&url)
+                    .map_err(|err| Failure::fatal(format!("invalid url \"{url}\": {err}")))?;
+
+                with_query(with_parameters(url, request.query(), context), target)
+            }
+        };
+
+        let mut builder = self
+            .client
+            .request(method(request.method()), url)
+            .timeout(timeout);
+
+        for (name, value) in request.headers().iter() {
+            if let Some(value) = context.text(value) {
+                builder = builder.header(name, value);
+            }
+        }
+
+        if let Some(body) = request.body() {
+            builder = with_body(builder, body, request, context).await?;
+        }
+
+        let sent_at = std::time::Instant::now();
+        let response = builder
+            .send()
+            .await
+            .map_err(|err| Failure {
+                reason: if err.is_timeout() {
+                    timed_out(timeout)
+                } else {
+                    format!("request failed: {err}")
+                },
+                status: None,
+                retryable: !err.is_builder(),
+                retry_after: None,
+            })?;
+
+        let status = response.status();
+        let url = response.url().clone();
+        let headers = response.headers().clone();
+
+        if !request.is_success_status(status.as_u16()) {
+            let retry_after = match status {
+                StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| retry::parse_retry_after(value, Utc::now())),
+                _ => None,
+            };
+
+            return Err(Failure {
+                reason: format!("unexpected status {status}"),
+                status: Some(status.as_u16()),
+                retryable: backoff.retries_status(status.as_u16()),
+                retry_after,
+            });
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(|err| Failure {
+                reason: if err.is_timeout() {
+                    timed_out(timeout)
+                } else {
+                    format!("failed to read response: {err}")
+                },
+                status: Some(status.as_u16()),
+                retryable: true,
+                retry_after: None,
+            })?;
+
+        Ok(Reply {
+            url,
+            status: status.as_u16(),
+            headers,
+            body: body.to_vec(),
+            latency: sent_at.elapsed(),
+        })
+    }
+}
+
+/// Sets the body and, unless the headers already do, its Content-Type.
+async fn with_body(
+    builder: reqwest::RequestBuilder,
+    body: &Body,
+    request: &Request,
+    context: &Context<'_>,
+) -> Result<reqwest::RequestBuilder, Failure> {
+    let builder = match body {
+        Body::Json(value) => return Ok(builder.json(&context.json(value))),
+        Body::Form(fields) => builder.body(form(fields, context)),
+        Body::Multipart(multipart) => {
+            let mut form = reqwest::multipart::Form::new();
+
+            for (name, text) in multipart
+                .fields
+                .iter()
+                .flat_map(|fields| pairs(fields, context))
+            {
+                form = form.text(name, text);
+            }
+
+            for (name, file) in &multipart.files {
+                let content = tokio::fs::read(&file.path)
+                    .await
+                    .map_err(|err| read_failure(&file.path, err))?;
+
+                let mut part = reqwest::multipart::Part::bytes(content)
+                    .mime_str(file.content_type())
+                    .map_err(|err| {
+                        Failure::fatal(format!(
+                            "invalid content type \"{}\": {err}",
+                            file.content_type()
+                        ))
+                    })?;
+
+                if let Some(file_name) = file.file_name() {
+                    part = part.file_name(file_name);
+                }
+
+                form = form.part(name.clone(), part);
+            }
+
+            // Sets the Content-Type with its boundary itself.
+            return Ok(builder.multipart(form));
+        }
+        Body::Xml(value) => {
+            builder.body(xml::to_xml(&context.json(value)).map_err(Failure::fatal)?)
+        }
+        Body::MsgPack(value) => builder.body(
+            rmp_serde::to_vec_named(&context.json(value))
+                .map_err(|err| Failure::fatal(format!("failed to write msgpack: {err}")))?,
+        ),
+        Body::Cbor(value) => {
+            let mut body = Vec::new();
+
+            ciborium::into_writer(&context.json(value), &mut body)
+                .map_err(|err| Failure::fatal(format!("failed to write cbor: {err}")))?;
+
+            builder.body(body)
+        }
+        Body::Text(text) => builder.body(
+            context
+                .text(&text.value)
+                .ok_or_else(|| no_value("text body", &text.value, context))?,
+        ),
+        Body::File(file) => {
+            let content = tokio::fs::File::open(&file.path)
+                .await
+                .map_err(|err| read_failure(&file.path, err))?;
+
+            builder.body(reqwest::Body::wrap_stream(
+                tokio_util::io::ReaderStream::new(content),
+            ))
+        }
+    };
+
+    let has_content_type = request
+        .headers()
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case(reqwest::header::CONTENT_TYPE.as_str()));
+
+    if has_content_type {
+        return Ok(builder);
+    }
+
+    Ok(builder.header(reqwest::header::CONTENT_TYPE, body.content_type()))
+}
+
+fn timed_out(timeout: Duration) -> String {
+    format!(
+        "request timed out after {}",
+        humantime::format_duration(timeout)
+    )
+}
+
+/// Names what left `value`, rendered as the request's `part`, without text.
+fn no_value(part: &str, value: &Value, context: &Context<'_>) -> Failure {
+    Failure::fatal(match context.missing(value) {
+        Some(missing) => format!("{part} {missing} has no value"),
+        None => format!("{part} is not text"),
+    })
+}
+
+fn read_failure(path: &std::path::Path, err: std::io::Error) -> Failure {
+    Failure::fatal(format!("failed to read {}: {err}", path.display()))
+}
+
+/// The rendered fields of a form, an array giving one pair per item.
+fn pairs(fields: &Value, context: &Context<'_>) -> Vec<(String, String)> {
+    let Value::Object(fields) = fields else {
+        return vec![];
+    };
+
+    fields
+        .iter()
+        .flat_map(|(name, value)| {
+            context
+                .texts(value)
+                .into_iter()
+                .map(move |text| (name.clone(), text))
+        })
+        .collect()
+}
+
+fn form(fields: &Value, context: &Context<'_>) -> String {
+    url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs(fields, context))
+        .finish()
+}
+
+/// Checks a reply against the task's assertions and takes its `extract`
+/// values, reading it as JSON if anything needs it.
+fn accept(
+    task: &Task,
+    reply: &Reply,
+    reads_json: bool,
+) -> Result<(Option<serde_json::Value>, state::Values), String> {
+    let response =
+        if reads_json || !task.extract().is_empty() || assertion::reads_json(task.assertions()) {
+            Some(json(&reply.body)?)
+        } else {
+            None
+        };
+
+    assertion::check(task.assertions(), reply, response.as_ref())?;
+
+    let state = response
+        .as_ref()
+        .map(|response| extract(task.extract(), response))
+        .unwrap_or_default();
+
+    Ok((response, state))
+}
+
+/// Appends the request's `query` to `url` in the order written.
+fn with_parameters(mut url: Url, query: &Query, context: &Context<'_>) -> Url {
+    let pairs = query
+        .iter()
+        .flat_map(|(name, value)| {
+            context
+                .texts(value)
+                .into_iter()
+                .map(move |text| pair(name, &text))
+        });
+
+    let query = url
+        .query()
+        .filter(|query| !query.is_empty())
+        .map(String::from)
+        .into_iter()
+        .chain(pairs)
+        .collect::<Vec<_>>()
+        .join("&");
+
+    if !query.is_empty() {
+        url.set_query(Some(&query));
+    }
+
+    url
+}
+
+/// Sets the query parameters of a `Target::Query` on `url`, replacing the
+/// ones of the same name. The rest of the query is kept as written.
+fn with_query(mut url: Url, target: &Target) -> Url {
+    let Target::Query(query) = target else {
+        return url;
+    };
+
+    let kept = url
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|segment| {
+            let (name, _) = url::form_urlencoded::parse(segment.as_bytes())
+                .next()
+                .unwrap_or_default();
+
+            !segment.is_empty()
+                && !query
+                    .iter()
+                    .any(|(param, _)| *param == name)
+        })
+        .map(String::from);
+
+    let query = kept
+        .chain(
+            query
+                .iter()
+                .map(|(name, value)| pair(name, value)),
+        )
+        .collect::<Vec<_>>()
+        .join("&");
+
+    url.set_query(Some(&query));
+
+    url
+}
+
+/// A query parameter with name and value percent-encoded, so a space is
+/// sent as `%20` rather than the `+` of forms.
+fn pair(name: &str, value: &str) -> String {
+    format!(
+        "{}={}",
+        utf8_percent_encode(name, COMPONENT),
+        utf8_percent_encode(value, COMPONENT)
+    )
+}
+
+fn json(body: &[u8]) -> Result<serde_json::Value, String> {
+    serde_json::from_slice(body).map_err(|err| format!("response is not JSON: {err}"))
+}
+
+/// Takes the task's `extract` values from a JSON response. Values the
+/// response lacks are left out and so keep their previous state.
+fn extract(extract: &HashMap<String, Extract>, response: &serde_json::Value) -> state::Values {
+    extract
+        .iter()
+        .filter_map(|(name, extract)| {
+            extract
+                .path
+                .find(response)
+                .map(|value| (name.clone(), value))
+        })
+        .collect()
+}
+
+fn method(method: &Method) -> reqwest::Method {
+    match method {
+        Method::Get => reqwest::Method::GET,
+        Method::Post => reqwest::Method::POST,
+        Method::Delete => reqwest::Method::DELETE,
+        Method::Put => reqwest::Method::PUT,
+        Method::Patch => reqwest::Method::PATCH,
+        Method::Head => reqwest::Method::HEAD,
+        Method::Options => reqwest::Method::OPTIONS,
+        Method::Trace => reqwest::Method::TRACE,
+        Method::Connect => reqwest::Method::CONNECT,
+        // Validated as a token when the config is loaded.
+        Method::Custom(method) => reqwest::Method::from_bytes(method.as_bytes())
+            .expect("extension method is a valid token"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::fixtures::{run, task};
+    use chrono::{TimeZone, Utc};
+    use wiremock::matchers::{
+        body_json, body_string, body_string_contains, header, header_regex, method, path,
+        query_param, query_param_is_missing,
+    };
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_execute_renders_headers_and_body() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/load"))
+            .and(header("X-Custom-Key", "My Custom Key"))
+            .and(header("X-Execute-Time", "2026-01-01T10:05:00Z"))
+            .and(header("X-Last-Execute-Time", "2026-01-01T10:00:00Z"))
+            .and(body_json(serde_json::json!({
+                "field1": "hello",
+                "field2": {"field1_1": 100},
+                "field3": [{"field1": false}, true],
+                "field4": null,
+                "last_execute_time": "2026-01-01T10:00:00Z",
+                "execute_time": "2026-01-01T10:05:00Z",
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: POST
+            url: {}/load
+            schedule: \"*/5 * * * *\"
+            headers:
+              X-Custom-Key:
+                type: string
+                value: My Custom Key
+              X-Last-Execute-Time:
+                type: source
+                source: last_execute_time
+              X-Execute-Time:
+                type: source
+                source: execute_time
+            body:
+              json:
+                type: object
+                properties:
+                  field1:
+                    type: string
+                    value: hello
+                  field2:
+                    type: object
+                    properties:
+                      field1_1:
+                        type: integer
+                        value: 100
+                  field3:
+                    type: array
+                    items:
+                      - type: object
+                        properties:
+                          field1:
+                            type: boolean
+                            value: false
+                      - type: boolean
+                        value: true
+                  field4:
+                    type: \"null\"
+                  last_execute_time:
+                    type: source
+                    source: last_execute_time
+                  execute_time:
+                    type: source
+                    source: execute_time",
+            server.uri()
+        ));
+
+        assert_eq!(
+            Outcome::Success,
+            HttpExecutor::default()
+                .execute(&task, &run())
+                .await
+                .outcome
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_first_run_sends_null_last_execute_time() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(body_json(serde_json::json!({"last_execute_time": null})))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: GET
+            url: {}/load
+            schedule: \"*/5 * * * *\"
+            headers:
+              X-Last-Execute-Time:
+                type: source
+                source: last_execute_time
+            body:
+              json:
+                type: object
+                properties:
+                  last_execute_time:
+                    type: source
+                    source: last_execute_time",
+            server.uri()
+        ));
+
+        let run = Run {
+            last_scheduled_at: None,
+            ..run()
+        };
+
+        assert_eq!(
+            Outcome::Success,
+            HttpExecutor::default()
+                .execute(&task, &run)
+                .await
+                .outcome
+        );
+
+        let requests = server
+            .received_requests()
+            .await
+            .unwrap();
+        assert!(!requests[0]
+            .headers
+            .contains_key("X-Last-Execute-Time"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_checks_success_status_codes() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/ok"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(path("/created"))
+            .respond_with(ResponseTemplate::new(201))
+            .mount(&server)
+            .await;
+        Mock::given(path("/error"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let executor = HttpExecutor::default();
+        let outcome = |url: &str, codes: &str| {
+            let task = task(&format!(
+                "
+                name: load_data
+                method: GET
+                url: {}{url}
+                schedule: \"*/5 * * * *\"
+                success_status_codes: {codes}",
+                server.uri()
+            ));
+
+            let executor = &executor;
+            async move {
+                executor
+                    .execute(&task, &run())
+                    .await
+                    .outcome
+            }
+        };
+
+        assert_eq!(Outcome::Success, outcome("/ok", "[]").await);
+        assert_eq!(
+            Outcome::Failure(String::from("unexpected status 201 Created")),
+            outcome("/created", "[]").await
+        );
+        assert_eq!(Outcome::Success, outcome("/created", "[200, 201]").await);
+        assert_eq!(
+            Outcome::Success,
+            outcome("/created", "[2xx, \"!200\"]").await
+        );
+        assert_eq!(
+            Outcome::Failure(String::from("unexpected status 200 OK")),
+            outcome("/ok", "[200-299, \"!200\"]").await
+        );
+        assert_eq!(
+            Outcome::Failure(String::from("unexpected status 200 OK")),
+            outcome("/ok", "[201]").await
+        );
+        assert_eq!(
+            Outcome::Failure(String::from("unexpected status 500 Internal Server Error")),
+            outcome("/error", "[]").await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_sends_extension_methods() {
+        let server = MockServer::start().await;
+
+        for verb in ["HEAD", "OPTIONS", "PROPFIND", "MKCOL"] {
+            Mock::given(method(verb))
+                .and(path("/dav"))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let executor = HttpExecutor::default();
+
+        for verb in ["HEAD", "OPTIONS", "PROPFIND", "MKCOL"] {
+            let task = task(&format!(
+                "
+                name: load_data
+                method: {verb}
+                url: {}/dav
+                schedule: \"*/5 * * * *\"",
+                server.uri()
+            ));
+
+            assert_eq!(
+                Outcome::Success,
+                executor
+                    .execute(&task, &run())
+                    .await
+                    .outcome,
+                "{verb}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_encodes_query() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/load"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: GET
+            url: {}/load?kind=all
+            schedule: \"*/5 * * * *\"
+            query:
+              since:
+                type: source
+                source: last_execute_time
+              until:
+                type: source
+                source: execute_time
+              q:
+                type: string
+                value: a&b c=d
+              tag:
+                type: array
+                items:
+                  - type: string
+                    value: x
+                  - type: integer
+                    value: 2
+              cursor:
+                type: source
+                source: state.cursor
+            extract:
+              cursor: $.cursor",
+            server.uri()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(Outcome::Success, report.outcome);
+
+        let requests = server
+            .received_requests()
+            .await
+            .unwrap();
+        assert_eq!(
+            Some("kind=all&since=2026-01-01T10%3A00%3A00Z&until=2026-01-01T10%3A05%3A00Z&q=a%26b%20c%3Dd&tag=x&tag=2"),
+            requests[0].url.query()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_renders_templates() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/reports/2026-01-01/ACME%20CORP"))
+            .and(query_param("since", "10:00"))
+            .and(header("Authorization", "Bearer t0k3n"))
+            .and(body_json(serde_json::json!({
+                "title": "Report for 2026-01-01, page 1",
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: POST
+            url:
+              type: template
+              value: \"{}/reports/{{{{ execute_time | date('%Y-%m-%d') }}}}/{{{{ state.customer | upper | urlencode }}}}\"
+            schedule: \"*/5 * * * *\"
+            headers:
+              Authorization:
+                type: template
+                value: \"Bearer {{{{ state.token }}}}\"
+              X-Missing:
+                type: template
+                value: \"{{{{ state.missing }}}}\"
+            query:
+              since:
+                type: template
+                value: \"{{{{ last_execute_time | date('%H:%M') }}}}\"
+            body:
+              json:
+                type: object
+                properties:
+                  title:
+                    type: template
+                    value: \"Report for {{{{ execute_time | date('%F') }}}}, page {{{{ state.page | default('1') }}}}\"
+            extract:
+              token: $.token
+              customer: $.customer
+              missing: $.missing
+              page: $.page",
+            server.uri()
+        ));
+
+        let run = Run {
+            state: state::Values::from([
+                (String::from("token"), serde_json::json!("t0k3n")),
+                (String::from("customer"), serde_json::json!("acme corp")),
+            ]),
+            ..run()
+        };
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run)
+            .await;
+
+        assert_eq!(Outcome::Success, report.outcome);
+
+        let requests = server
+            .received_requests()
+            .await
+            .unwrap();
+        assert!(!requests[0]
+            .headers
+            .contains_key("X-Missing"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_formats_dates() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/load"))
+            .and(query_param("since", "1767261600"))
+            .and(body_json(serde_json::json!({
+                "from": "2025-12-31T00:00:00+01:00",
+                "to": "2026-01-01T00:00:00+01:00",
+                "day": "20251231",
+                "at": 1767261900000_i64,
+                "local": "2026-01-01T11:05:00+01:00",
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: POST
+            url: {}/load
+            schedule: \"*/5 * * * *\"
+            query:
+              since:
+                type: source
+                source: last_execute_time
+                format: unix
+            body:
+              json:
+                type: object
+                properties:
+                  from:
+                    type: source
+                    source: execute_time
+                    format: \"%Y-%m-%dT00:00:00%:z\"
+                    timezone: Europe/Berlin
+                    offset: -1d
+                  to:
+                    type: source
+                    source: execute_time
+                    format: \"%Y-%m-%dT00:00:00%:z\"
+                    timezone: Europe/Berlin
+                  day:
+                    type: source
+                    source: execute_time
+                    format: \"%Y%m%d\"
+                    timezone: America/New_York
+                    offset: -1d
+                  at:
+                    type: source
+                    source: execute_time
+                    format: unix_ms
+                  local:
+                    type: source
+                    source: execute_time
+                    timezone: Europe/Berlin",
+            server.uri()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(Outcome::Success, report.outcome);
+    }
+
+    #[tokio::test]
+    async fn test_execute_formats_dates_across_dst() {
+        let server = MockServer::start().await;
+
+        // Clocks in Berlin moved from 02:00 CET to 03:00 CEST on 2026-03-29.
+        Mock::given(path("/load"))
+            .and(body_json(serde_json::json!({
+                "from": "2026-03-29T00:00:00+01:00",
+                "to": "2026-03-30T00:00:00+02:00",
+                "day_before": "2026-03-29T00:00:00+01:00",
+                "earlier": "2026-03-29T00:00:00+01:00",
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: POST
+            url: {}/load
+            schedule: \"0 0 * * *\"
+            body:
+              json:
+                from:
+                  type: source
+                  source: execute_time
+                  timezone: Europe/Berlin
+                  offset: -1d
+                  truncate: day
+                to:
+                  type: source
+                  source: execute_time
+                  timezone: Europe/Berlin
+                  truncate: day
+                day_before:
+                  type: source
+                  source: execute_time
+                  timezone: Europe/Berlin
+                  offset: -1d
+                earlier:
+                  type: source
+                  source: last_execute_time
+                  timezone: Europe/Berlin
+                  truncate: day",
+            server.uri()
+        ));
+
+        // Midnight in Berlin on 2026-03-30, and 06:00 on the day before.
+        let run = Run {
+            scheduled_at: Utc
+                .with_ymd_and_hms(2026, 3, 29, 22, 0, 0)
+                .unwrap(),
+            last_scheduled_at: Some(
+                Utc.with_ymd_and_hms(2026, 3, 29, 4, 0, 0)
+                    .unwrap(),
+            ),
+            state: state::Values::new(),
+        };
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run)
+            .await;
+
+        assert_eq!(Outcome::Success, report.outcome);
+    }
+
+    #[tokio::test]
+    async fn test_execute_sends_form_and_text_bodies() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/form"))
+            .and(header("Content-Type", "application/x-www-form-urlencoded"))
+            .and(body_string(
+                "since=2026-01-01T10%3A00%3A00Z&until=2026-01-01T10%3A05%3A00Z&tag=a+b&tag=1",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/text"))
+            .and(header("Content-Type", "text/csv"))
+            .and(body_string("day,2026-01-01"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let form = task(&format!(
+            "
+            name: load_data
+            method: POST
+            url: {}/form
+            schedule: \"*/5 * * * *\"
+            body:
+              form:
+                type: object
+                properties:
+                  since:
+                    type: source
+                    source: last_execute_time
+                  until:
+                    type: source
+                    source: execute_time
+                  tag:
+                    type: array
+                    items:
+                      - type: string
+                        value: a b
+                      - type: integer
+                        value: 1
+                  cursor:
+                    type: source
+                    source: state.cursor
+            extract:
+              cursor: $.cursor",
+            server.uri()
+        ));
+        let text = task(&format!(
+            "
+            name: load_data
+            method: POST
+            url: {}/text
+            schedule: \"*/5 * * * *\"
+            body:
+              text:
+                content_type: text/csv
+                value:
+                  type: template
+                  value: \"day,{{{{ execute_time | date('%F') }}}}\"",
+            server.uri()
+        ));
+
+        for task in [form, text] {
+            let report = HttpExecutor::default()
+                .execute(&task, &run())
+                .await;
+
+            assert_eq!(Outcome::Success, report.outcome, "{report:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_fails_on_empty_text_body() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/text"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: POST
+            url: {}/text
+            schedule: \"*/5 * * * *\"
+            body:
+              text:
+                value:
+                  type: source
+                  source: last_execute_time",
+            server.uri()
+        ));
+
+        let run = Run {
+            last_scheduled_at: None,
+            ..run()
+        };
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run)
+            .await;
+
+        assert_eq!(
+            Outcome::Failure(String::from(
+                "text body source last_execute_time has no value"
+            )),
+            report.outcome
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_sends_multipart_and_file_bodies() {
+        let server = MockServer::start().await;
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("report.csv");
+        std::fs::write(&file, "id,total\n1,42\n").unwrap();
+
+        Mock::given(path("/multipart"))
+            .and(header_regex("Content-Type", "^multipart/form-data; boundary="))
+            .and(body_string_contains("name=\"kind\"\r\n\r\ndaily\r\n"))
+            .and(body_string_contains(
+                "name=\"report\"; filename=\"report.csv\"\r\nContent-Type: text/csv\r\n\r\nid,total\n1,42\n",
+            ))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/file"))
+            .and(header("Content-Type", "application/vnd.ms-excel"))
+            .and(body_string("id,total\n1,42\n"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let multipart = task(&format!(
+            "
+            name: load_data
+            method: POST
+            url: {}/multipart
+            schedule: \"*/5 * * * *\"
+            body:
+              multipart:
+                fields:
+                  type: object
+                  properties:
+                    kind:
+                      type: string
+                      value: daily
+                files:
+                  report:
+                    path: {}
+                    content_type: text/csv",
+            server.uri(),
+            file.display()
+        ));
+        // The headers' Content-Type wins over the file's.
+        let upload = task(&format!(
+            "
+            name: load_data
+            method: PUT
+            url: {}/file
+            schedule: \"*/5 * * * *\"
+            headers:
+              Content-Type:
+                type: string
+                value: application/vnd.ms-excel
+            body:
+              file:
+                path: {}
+                content_type: text/csv",
+            server.uri(),
+            file.display()
+        ));
+
+        for task in [multipart, upload] {
+            let report = HttpExecutor::default()
+                .execute(&task, &run())
+                .await;
+
+            assert_eq!(Outcome::Success, report.outcome, "{report:?}");
+        }
+
+        let missing = task(&format!(
+            "
+            name: load_data
+            method: PUT
+            url: {}/file
+            schedule: \"*/5 * * * *\"
+            body:
+              file:
+                path: {}",
+            server.uri(),
+            dir.path()
+                .join("missing.csv")
+                .display()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&missing, &run())
+            .await;
+
+        assert!(matches!(
+            report.outcome,
+            Outcome::Failure(reason) if reason.starts_with("failed to read ")
+        ));
+        assert_eq!(1, report.attempts.len());
+    }
+
+    #[tokio::test]
+    async fn test_execute_sends_xml_msgpack_and_cbor_bodies() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/xml"))
+            .and(header("Content-Type", "application/xml"))
+            .and(body_string(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+                 <report date=\"2026-01-01T10:05:00Z\"><line>a</line><line>b</line></report>",
+            ))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/msgpack"))
+            .and(header("Content-Type", "application/msgpack"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/cbor"))
+            .and(header("Content-Type", "application/cbor"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let body = "
+                    type: object
+                    properties:
+                      \"@date\":
+                        type: source
+                        source: execute_time
+                      line:
+                        type: array
+                        items:
+                          - type: string
+                            value: a
+                          - type: string
+                            value: b";
+
+        for format in ["xml", "msgpack", "cbor"] {
+            let task = task(&format!(
+                "
+            name: load_data
+            method: POST
+            url: {}/{format}
+            schedule: \"*/5 * * * *\"
+            body:
+              {format}:
+                type: object
+                properties:
+                  report:{body}",
+                server.uri()
+            ));
+
+            let report = HttpExecutor::default()
+                .execute(&task, &run())
+                .await;
+
+            assert_eq!(Outcome::Success, report.outcome, "{report:?}");
+        }
+
+        let requests = server
+            .received_requests()
+            .await
+            .unwrap();
+        let expected = serde_json::json!({
+            "report": {"@date": "2026-01-01T10:05:00Z", "line": ["a", "b"]},
+        });
+
+        assert_eq!(
+            expected,
+            rmp_serde::from_slice::<serde_json::Value>(&requests[1].body).unwrap()
+        );
+        assert_eq!(
+            expected,
+            ciborium::from_reader::<serde_json::Value, _>(requests[2].body.as_slice()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_keeps_key_order() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/load"))
+            .and(body_string(
+                r#"{"zone":"eu","id":1,"meta":{"version":2,"author":"ops"},"at":"2026-01-01T10:05:00Z"}"#,
+            ))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: POST
+            url: {}/load
+            schedule: \"*/5 * * * *\"
+            headers:
+              X-Signature-C:
+                type: string
+                value: c
+              X-Signature-A:
+                type: string
+                value: a
+              X-Signature-B:
+                type: string
+                value: b
+            body:
+              json:
+                type: object
+                properties:
+                  zone:
+                    type: string
+                    value: eu
+                  id:
+                    type: integer
+                    value: 1
+                  meta:
+                    type: object
+                    properties:
+                      version:
+                        type: integer
+                        value: 2
+                      author:
+                        type: string
+                        value: ops
+                  at:
+                    type: source
+                    source: execute_time",
+            server.uri()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(Outcome::Success, report.outcome, "{report:?}");
+
+        let requests = server
+            .received_requests()
+            .await
+            .unwrap();
+        let signatures: Vec<&str> = requests[0]
+            .headers
+            .keys()
+            .map(|name| name.as_str())
+            .filter(|name| name.starts_with("x-signature-"))
+            .collect();
+
+        assert_eq!(
+            vec!["x-signature-c", "x-signature-a", "x-signature-b"],
+            signatures
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_reports_connection_errors() {
+        let task = task(
+            "
+            name: load_data
+            method: GET
+            url: http://127.0.0.1:1/load
+            schedule: \"*/5 * * * *\"",
+        );
+
+        let outcome = HttpExecutor::default()
+            .execute(&task, &run())
+            .await
+            .outcome;
+
+        assert!(
+            matches!(&outcome, Outcome::Failure(reason) if reason.starts_with("request failed")),
+            "{outcome:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_times_out_slow_responses() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/load"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: GET
+            url: {}/load
+            schedule: \"*/5 * * * *\"
+            timeout: 50ms",
+            server.uri()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(
+            Outcome::Failure(String::from("request timed out after 50ms")),
+            report.outcome
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_retries_and_records_attempts() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/load"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/load"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: GET
+            url: {}/load
+            schedule: \"*/5 * * * *\"
+            retry:
+              max_attempts: 4
+              initial_delay: 10ms
+              jitter: none",
+            server.uri()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(Outcome::Success, report.outcome);
+        assert_eq!(
+            vec![Some(503), Some(503), Some(200)],
+            report
+                .attempts
+                .iter()
+                .map(|attempt| attempt.status)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Outcome::Failure(String::from("unexpected status 503 Service Unavailable")),
+            report.attempts[0].outcome
+        );
+        assert!(report.attempts[1].started_at >= report.attempts[0].finished_at);
+    }
+
+    #[tokio::test]
+    async fn test_execute_gives_up_after_max_attempts() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/load"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: GET
+            url: {}/load
+            schedule: \"*/5 * * * *\"
+            retry:
+              max_attempts: 2
+              initial_delay: 10ms",
+            server.uri()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(
+            Outcome::Failure(String::from("unexpected status 500 Internal Server Error")),
+            report.outcome
+        );
+        assert_eq!(2, report.attempts.len());
+    }
+
+    #[tokio::test]
+    async fn test_execute_names_url_placeholder_without_value() {
+        let task = task(
+            "
+            name: load_data
+            method: GET
+            url:
+              type: template
+              value: \"http://localhost:3030/customers/{{ state.customer }}\"
+            schedule: \"*/5 * * * *\"
+            extract:
+              customer: $.customer",
+        );
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(
+            Outcome::Failure(String::from("url placeholder state.customer has no value")),
+            report.outcome
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_reads_and_extracts_state() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/load"))
+            .and(header("X-Cursor", "page-2"))
+            .and(body_json(serde_json::json!({"max_id": 41})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "next_page_token": "page-3",
+                "items": [{"id": 42}, {"id": 43}],
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: GET
+            url: {}/load
+            schedule: \"*/5 * * * *\"
+            headers:
+              X-Cursor:
+                type: source
+                source: state.cursor
+            body:
+              json:
+                type: object
+                properties:
+                  max_id:
+                    type: source
+                    source: state.max_id
+            extract:
+              cursor: /next_page_token
+              max_id:
+                path: $.items[-1].id
+                default: 0
+              missing: $.missing",
+            server.uri()
+        ));
+
+        let run = Run {
+            state: state::Values::from([
+                (String::from("cursor"), serde_json::json!("page-2")),
+                (String::from("max_id"), serde_json::json!(41)),
+            ]),
+            ..run()
+        };
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run)
+            .await;
+
+        assert_eq!(Outcome::Success, report.outcome);
+        assert_eq!(
+            state::Values::from([
+                (String::from("cursor"), serde_json::json!("page-3")),
+                (String::from("max_id"), serde_json::json!(43)),
+            ]),
+            report.state
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_fails_on_failed_assertion() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/load"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "status": "error",
+                "next_page_token": "page-2",
+            })))
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: GET
+            url: {}/load
+            schedule: \"*/5 * * * *\"
+            extract:
+              cursor: $.next_page_token
+            assert:
+              - json: $.status
+                equals: ok
+              - header: Content-Type
+                matches: ^application/json",
+            server.uri()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(
+            Outcome::Failure(String::from(
+                "assertion failed: $.status is \"error\", expected \"ok\""
+            )),
+            report.outcome
+        );
+        assert!(report.state.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_follows_cursor_pages() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/items"))
+            .and(query_param("page_token", "b"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "items": [{"id": 3}],
+                "next": null,
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/items"))
+            .and(query_param("page_token", "a"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "items": [{"id": 2}],
+                "next": "b",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/items"))
+            .and(query_param_is_missing("page_token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "items": [{"id": 0}, {"id": 1}],
+                "next": "a",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: GET
+            url: {}/items?kind=all
+            schedule: \"*/5 * * * *\"
+            extract:
+              max_id: $.items[-1].id
+            pagination:
+              type: cursor
+              cursor: $.next
+              param: page_token
+              items: $.items",
+            server.uri()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(Outcome::Success, report.outcome);
+        assert_eq!(
+            vec![Some(2), Some(1), Some(1)],
+            report
+                .pages
+                .iter()
+                .map(|page| page.items)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            format!("{}/items?kind=all&page_token=b", server.uri()),
+            report.pages[2].url
+        );
+        assert_eq!(
+            state::Values::from([(String::from("max_id"), serde_json::json!(3))]),
+            report.state
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_counts_pages_and_offsets() {
+        let server = MockServer::start().await;
+
+        for (page, items) in [("1", 2), ("2", 2), ("3", 1)] {
+            Mock::given(path("/pages"))
+                .and(query_param("page", page))
+                .and(query_param("per_page", "2"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({"data": vec![0; items]})),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        for (offset, items) in [("0", 2), ("2", 0)] {
+            Mock::given(path("/offsets"))
+                .and(query_param("offset", offset))
+                .and(query_param("limit", "2"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({"data": vec![0; items]})),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let executor = HttpExecutor::default();
+        let pages = |url: &str, pagination: &str| {
+            let task = task(&format!(
+                "
+                name: load_data
+                method: GET
+                url: {}{url}
+                schedule: \"*/5 * * * *\"
+                pagination: {{items: /data, {pagination}}}",
+                server.uri()
+            ));
+
+            let executor = &executor;
+            async move {
+                let report = executor
+                    .execute(&task, &run())
+                    .await;
+
+                assert_eq!(Outcome::Success, report.outcome);
+                report.pages.len()
+            }
+        };
+
+        assert_eq!(
+            3,
+            pages("/pages", "type: page, size: 2, size_param: per_page").await
+        );
+        assert_eq!(
+            2,
+            pages("/offsets?filter=a+b&offset=9", "type: offset, limit: 2").await
+        );
+
+        let requests = server
+            .received_requests()
+            .await
+            .unwrap();
+        assert_eq!(
+            Some("filter=a+b&offset=2&limit=2"),
+            requests
+                .last()
+                .unwrap()
+                .url
+                .query()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_follows_link_header_up_to_max_pages() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/items"))
+            .respond_with(
+                ResponseTemplate::new(200).insert_header("Link", "</items?after=x>; rel=\"next\""),
+            )
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: GET
+            url: {}/items
+            schedule: \"*/5 * * * *\"
+            pagination:
+              type: link
+              max_pages: 3",
+            server.uri()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(Outcome::Success, report.outcome);
+        assert_eq!(
+            vec![
+                Page {
+                    url: format!("{}/items", server.uri()),
+                    status: 200,
+                    items: None,
+                },
+                Page {
+                    url: format!("{}/items?after=x", server.uri()),
+                    status: 200,
+                    items: None,
+                },
+                Page {
+                    url: format!("{}/items?after=x", server.uri()),
+                    status: 200,
+                    items: None,
+                },
+            ],
+            report.pages
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_refuses_link_header_to_another_origin() {
+        let server = MockServer::start().await;
+        let other = MockServer::start().await;
+
+        Mock::given(path("/items"))
+            .respond_with(ResponseTemplate::new(200).insert_header(
+                "Link",
+                format!("<{}/items?after=x>; rel=\"next\"", other.uri()),
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/items"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&other)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: GET
+            url: {}/items
+            schedule: \"*/5 * * * *\"
+            headers:
+              Authorization: Bearer secret
+            pagination:
+              type: link",
+            server.uri()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(
+            Outcome::Failure(format!(
+                "next link \"{}/items?after=x\" leaves {}, refusing to follow it",
+                other.uri(),
+                server.uri()
+            )),
+            report.outcome
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_fails_on_failed_page() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/items"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        Mock::given(path("/items"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([1])))
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: GET
+            url: {}/items
+            schedule: \"*/5 * * * *\"
+            pagination:
+              type: page
+              items: $",
+            server.uri()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(
+            Outcome::Failure(String::from(
+                "page 2 failed: unexpected status 500 Internal Server Error"
+            )),
+            report.outcome
+        );
+        assert_eq!(1, report.pages.len());
+    }
+
+    #[tokio::test]
+    async fn test_execute_does_not_retry_other_statuses() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/load"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: GET
+            url: {}/load
+            schedule: \"*/5 * * * *\"
+            retry:
+              max_attempts: 3
+              initial_delay: 10ms",
+            server.uri()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(1, report.attempts.len());
+    }
+
+    #[tokio::test]
+    async fn test_execute_honours_retry_after() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/load"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/load"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: load_data
+            method: GET
+            url: {}/load
+            schedule: \"*/5 * * * *\"
+            retry:
+              initial_delay: 10ms",
+            server.uri()
+        ));
+
+        let report = HttpExecutor::default()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(Outcome::Success, report.outcome);
+        assert!(
+            report.attempts[1].started_at - report.attempts[0].finished_at
+                >= chrono::Duration::seconds(1)
+        );
+    }
+}
@@ -0,0 +1,109 @@
+pub mod assertion;
+#[cfg(test)]
+mod fixtures;
+pub mod http;
+pub mod pagination;
+pub mod pipeline;
+pub mod render;
+pub mod retry;
+pub mod xml;
+
+use crate::config::tasks;
+use crate::state;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A single due run of a task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub scheduled_at: DateTime<Utc>,
+    pub last_scheduled_at: Option<DateTime<Utc>>,
+    /// What the task extracted so far, over its defaults.
+    pub state: state::Values,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum Outcome {
+    Success,
+    Failure(String),
+    /// Not run at all, e.g. because the previous run was still in flight.
+    Skipped(String),
+    /// Cancelled in flight in favour of a newer run.
+    Replaced,
+}
+
+/// One try at a run. A run takes several when it is retried.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Attempt {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Response status, if a response was received.
+    pub status: Option<u16>,
+    pub outcome: Outcome,
+    /// The pipeline step the attempt belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<String>,
+}
+
+/// One page of a paginated run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Page {
+    pub url: String,
+    pub status: u16,
+    /// How many items the page held, if the pagination names them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub items: Option<usize>,
+}
+
+/// The result of a run together with every attempt it took.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub outcome: Outcome,
+    pub attempts: Vec<Attempt>,
+    /// Every page received, empty unless the task is paginated.
+    pub pages: Vec<Page>,
+    /// Values extracted from the response, to be merged into the state.
+    pub state: state::Values,
+}
+
+#[async_trait]
+pub trait Executor: Send + Sync {
+    async fn execute(&self, task: &tasks::Task, run: &Run) -> Report;
+}
+
+/// Routes every run to the executor of its task kind.
+pub struct Dispatcher {
+    http: http::HttpExecutor,
+    pipeline: pipeline::PipelineExecutor,
+}
+
+impl Default for Dispatcher {
+    fn default() -> Self {
+        let http = http::HttpExecutor::default();
+
+        Self {
+            pipeline: pipeline::PipelineExecutor::new(http.clone()),
+            http,
+        }
+    }
+}
+
+#[async_trait]
+impl Executor for Dispatcher {
+    async fn execute(&self, task: &tasks::Task, run: &Run) -> Report {
+        match task {
+            tasks::Task::Http(task) => {
+                self.http
+                    .execute(task, run)
+                    .await
+            }
+            tasks::Task::Pipeline(task) => {
+                self.pipeline
+                    .execute(task, run)
+                    .await
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use super::http::{Reply, Target};
+use crate::config::pagination::{Pagination, Style};
+
+/// Works out which page to request next from the one just received.
+pub struct Pager<'a> {
+    pagination: &'a Pagination,
+    /// Page number or offset of the last page requested.
+    position: u64,
+}
+
+impl<'a> Pager<'a> {
+    pub fn new(pagination: &'a Pagination) -> Self {
+        let position = match &pagination.style {
+            Style::Page { start, .. } => *start,
+            _ => 0,
+        };
+
+        Self {
+            pagination,
+            position,
+        }
+    }
+
+    /// Whether pages have to be read as JSON.
+    pub fn reads_json(&self) -> bool {
+        !matches!(self.pagination.style, Style::Link { items: None })
+    }
+
+    pub fn first(&self) -> Target {
+        match &self.pagination.style {
+            Style::Cursor { .. } | Style::Link { .. } => Target::Request,
+            Style::Page { .. } | Style::Offset { .. } => Target::Query(self.query()),
+        }
+    }
+
+    /// Counts the items of a page, a page without them holds none.
+    pub fn items(&self, response: Option<&serde_json::Value>) -> Result<Option<usize>, String> {
+        let (Some(path), Some(response)) = (self.pagination.items(), response) else {
+            return Ok(None);
+        };
+
+        match path.find(response) {
+            None | Some(serde_json::Value::Null) => Ok(Some(0)),
+            Some(serde_json::Value::Array(items)) => Ok(Some(items.len())),
+            Some(_) => Err(format!("items at {path} are not an array")),
+        }
+    }
+
+    /// Returns where the page after `reply` is, or `None` after the last one.
+    pub fn next(
+        &mut self,
+        reply: &Reply,
+        response: Option<&serde_json::Value>,
+        items: Option<usize>,
+    ) -> Result<Option<Target>, String> {
+        match &self.pagination.style {
+            Style::Cursor { cursor, param, .. } => {
+                let value = response.and_then(|response| cursor.find(response));
+
+                let cursor = match value {
+                    None | Some(serde_json::Value::Null) => return Ok(None),
+                    Some(serde_json::Value::String(cursor)) if cursor.is_empty() => {
+                        return Ok(None)
+                    }
+                    Some(serde_json::Value::String(cursor)) => cursor,
+                    Some(serde_json::Value::Number(cursor)) => cursor.to_string(),
+                    Some(_) => {
+                        return Err(format!(
+                            "cursor at {cursor} is neither a string nor a number"
+                        ))
+                    }
+                };
+
+                Ok(Some(Target::Query(vec![(param.clone(), cursor)])))
+            }
+            Style::Page { size, .. } => {
+                let items = items.unwrap_or_default();
+
+                if items == 0 || size.is_some_and(|size| (items as u64) < size) {
+                    return Ok(None);
+                }
+
+                self.position += 1;
+
+                Ok(Some(Target::Query(self.query())))
+            }
+            Style::Offset { limit, .. } => {
+                let items = items.unwrap_or_default() as u64;
+
+                if items == 0 || items < *limit {
+                    return Ok(None);
+                }
+
+                self.position += items;
+
+                Ok(Some(Target::Query(self.query())))
+            }
+            Style::Link { .. } => {
+                let next = reply
+                    .headers
+                    .get_all(reqwest::header::LINK)
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .find_map(next_link);
+
+                let Some(next) = next else {
+                    return Ok(None);
+                };
+
+                let url = reply
+                    .url
+                    .join(next)
+                    .map_err(|err| format!("invalid next link \"{next}\": {err}"))?;
+
+                // The next page is sent with the task's headers, credentials
+                // included, so it must stay where the task sent them.
+                if url.origin() != reply.url.origin() {
+                    return Err(format!(
+                        "next link \"{url}\" leaves {}, refusing to follow it",
+                        reply
+                            .url
+                            .origin()
+                            .ascii_serialization()
+                    ));
+                }
+
+                Ok(Some(Target::Url(url)))
+            }
+        }
+    }
+
+    /// The query parameters of the page at `position`.
+    fn query(&self) -> Vec<(String, String)> {
+        match &self.pagination.style {
+            Style::Page {
+                param,
+                size,
+                size_param,
+                ..
+            } => {
+                let mut query = vec![(param.clone(), self.position.to_string())];
+
+                if let (Some(size), Some(size_param)) = (size, size_param) {
+                    query.push((size_param.clone(), size.to_string()));
+                }
+
+                query
+            }
+            Style::Offset {
+                param,
+                limit_param,
+                limit,
+                ..
+            } => vec![
+                (param.clone(), self.position.to_string()),
+                (limit_param.clone(), limit.to_string()),
+            ],
+            Style::Cursor { .. } | Style::Link { .. } => vec![],
+        }
+    }
+}
+
+/// Finds the `rel="next"` target of a `Link` header such as
+/// `<https://api.example.com/items?page=2>; rel="next", <...>; rel="last"`.
+fn next_link(header: &str) -> Option<&str> {
+    let mut rest = header;
+
+    while let Some(start) = rest.find('<') {
+        let end = start + rest[start..].find('>')?;
+        let target = &rest[start + 1..end];
+        let params_end = rest[end..]
+            .find('<')
+            .map_or(rest.len(), |next| end + next);
+
+        let is_next = rest[end + 1..params_end]
+            .split([';', ','])
+            .filter_map(|param| param.split_once('='))
+            .any(|(name, value)| {
+                name.trim()
+                    .eq_ignore_ascii_case("rel")
+                    && value
+                        .trim()
+                        .trim_matches('"')
+                        .split_ascii_whitespace()
+                        .any(|rel| rel.eq_ignore_ascii_case("next"))
+            });
+
+        if is_next {
+            return Some(target);
+        }
+
+        rest = &rest[params_end..];
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_link() {
+        assert_eq!(
+            Some("https://api.example.com/items?page=2"),
+            next_link(
+                "<https://api.example.com/items?page=1>; rel=\"prev\", <https://api.example.com/items?page=2>; rel=\"next\""
+            )
+        );
+        assert_eq!(Some("/items?page=3"), next_link("</items?page=3>;rel=next"));
+        assert_eq!(
+            Some("/items?a=1,2"),
+            next_link("</items?a=1,2>; title=\"x\"; rel=\"last next\"")
+        );
+        assert_eq!(None, next_link("</items?page=9>; rel=\"last\""));
+        assert_eq!(None, next_link(""));
+    }
+}
@@ -0,0 +1,340 @@
+use super::assertion;
+use super::http::{HttpExecutor, Reply, Target};
+use super::render::{Context, Steps};
+use super::{Outcome, Report, Run};
+use crate::config::pipeline::{Step, Task};
+use crate::state;
+use std::collections::HashMap;
+
+pub struct PipelineExecutor {
+    http: HttpExecutor,
+}
+
+impl PipelineExecutor {
+    pub fn new(http: HttpExecutor) -> Self {
+        Self { http }
+    }
+
+    /// Sends the steps in order. The first step that fails, after its
+    /// retries, fails the pipeline and names itself in the reason.
+    pub async fn execute(&self, task: &Task, run: &Run) -> Report {
+        let options = task.options();
+        let mut steps = Steps::new();
+        let mut attempts = Vec::new();
+
+        for step in task.steps() {
+            let first = attempts.len();
+            let result = self
+                .http
+                .send(
+                    step.request(),
+                    &Context::with_steps(run, &steps),
+                    &Target::Request,
+                    step.retry()
+                        .or(options.retry.as_ref()),
+                    options.timeout,
+                    &mut attempts,
+                )
+                .await;
+
+            for attempt in &mut attempts[first..] {
+                attempt.step = Some(String::from(step.name()));
+            }
+
+            match result.and_then(|reply| accept(step, &reply)) {
+                Ok(fields) => {
+                    steps.insert(String::from(step.name()), fields);
+                }
+                Err(reason) => {
+                    return Report {
+                        outcome: Outcome::Failure(format!(
+                            "step `{}` failed: {reason}",
+                            step.name()
+                        )),
+                        attempts,
+                        pages: vec![],
+                        state: state::Values::new(),
+                    }
+                }
+            }
+        }
+
+        Report {
+            outcome: Outcome::Success,
+            attempts,
+            pages: vec![],
+            state: state::Values::new(),
+        }
+    }
+}
+
+/// Checks the step's assertions and takes the fields it extracts. A field
+/// the response lacks takes its default, or fails the step without one.
+fn accept(step: &Step, reply: &Reply) -> Result<HashMap<String, serde_json::Value>, String> {
+    let response: Option<serde_json::Value> =
+        if !step.extract().is_empty() || assertion::reads_json(step.assertions()) {
+            Some(
+                serde_json::from_slice(&reply.body)
+                    .map_err(|err| format!("response is not JSON: {err}"))?,
+            )
+        } else {
+            None
+        };
+
+    assertion::check(step.assertions(), reply, response.as_ref())?;
+
+    let Some(response) = response else {
+        return Ok(HashMap::new());
+    };
+
+    step.extract()
+        .iter()
+        .map(|(field, extract)| {
+            extract
+                .path
+                .find(&response)
+                .or_else(|| extract.default.clone())
+                .map(|value| (field.clone(), value))
+                .ok_or_else(|| format!("no `{field}` at {} in the response", extract.path))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::fixtures::{run, task};
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn executor() -> PipelineExecutor {
+        PipelineExecutor::new(HttpExecutor::default())
+    }
+
+    #[tokio::test]
+    async fn test_execute_passes_extracted_fields_between_steps() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "secret",
+                "links": {"items": format!("{}/items", server.uri())},
+                "expires_in": 3600,
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/items"))
+            .and(header("X-Token", "secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"id": 7})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/confirm"))
+            .and(body_json(serde_json::json!({"id": 7, "expires_in": 3600})))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let task = task(&format!(
+            "
+            name: sync
+            schedule: \"@daily\"
+            steps:
+              - name: login
+                method: POST
+                url: {uri}/login
+                extract:
+                  token: /access_token
+                  items: /links/items
+                  expires_in: /expires_in
+              - name: load
+                method: GET
+                url:
+                  type: step
+                  step: login
+                  field: items
+                headers:
+                  X-Token:
+                    type: step
+                    step: login
+                    field: token
+                extract:
+                  id: /id
+              - name: confirm
+                method: POST
+                url: {uri}/confirm
+                body:
+                  json:
+                    type: object
+                    properties:
+                      id:
+                        type: step
+                        step: load
+                        field: id
+                      expires_in:
+                        type: step
+                        step: login
+                        field: expires_in",
+            uri = server.uri()
+        ));
+
+        let report = executor()
+            .execute(&task, &run())
+            .await;
+
+        assert_eq!(Outcome::Success, report.outcome);
+        assert_eq!(
+            vec![Some("login"), Some("load"), Some("confirm")],
+            report
+                .attempts
+                .iter()
+                .map(|attempt| attempt.step.as_deref())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_reports_failing_step() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/login"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .mount(&server)
+            .await;
+        Mock::given(path("/load"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        Mock::given(path("/confirm"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let pipeline = |extract: &str| {
+            task(&format!(
+                "
+                name: sync
+                schedule: \"@daily\"
+                steps:
+                  - name: login
+                    method: POST
+                    url: {uri}/login{extract}
+                  - name: load
+                    method: GET
+                    url: {uri}/load
+                  - name: confirm
+                    method: POST
+                    url: {uri}/confirm",
+                uri = server.uri()
+            ))
+        };
+
+        assert_eq!(
+            Outcome::Failure(String::from(
+                "step `load` failed: unexpected status 401 Unauthorized"
+            )),
+            executor()
+                .execute(&pipeline(""), &run())
+                .await
+                .outcome
+        );
+        assert_eq!(
+            Outcome::Failure(String::from(
+                "step `login` failed: no `token` at /access_token in the response"
+            )),
+            executor()
+                .execute(
+                    &pipeline(
+                        "
+                    extract:
+                      token: /access_token"
+                    ),
+                    &run()
+                )
+                .await
+                .outcome
+        );
+    }
+    #[tokio::test]
+    async fn test_execute_checks_step_assertions_retries_and_defaults() {
+        let server = MockServer::start().await;
+
+        Mock::given(path("/login"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/login"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "status": "locked",
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(path("/load"))
+            .and(header("X-Token", "anonymous"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pipeline = |assert: &str| {
+            task(&format!(
+                "
+                name: sync
+                schedule: \"@daily\"
+                steps:
+                  - name: login
+                    method: POST
+                    url: {uri}/login
+                    retry:
+                      max_attempts: 2
+                      initial_delay: 10ms
+                    extract:
+                      token:
+                        path: /access_token
+                        default: anonymous{assert}
+                  - name: load
+                    method: GET
+                    url: {uri}/load
+                    headers:
+                      X-Token:
+                        type: step
+                        step: login
+                        field: token",
+                uri = server.uri()
+            ))
+        };
+
+        let report = executor()
+            .execute(&pipeline(""), &run())
+            .await;
+
+        assert_eq!(Outcome::Success, report.outcome);
+        assert_eq!(3, report.attempts.len());
+
+        assert_eq!(
+            Outcome::Failure(String::from(
+                "step `login` failed: assertion failed: $.status is \"locked\", expected \"ok\""
+            )),
+            executor()
+                .execute(
+                    &pipeline(
+                        "
+                    assert:
+                      - json: $.status
+                        equals: ok"
+                    ),
+                    &run()
+                )
+                .await
+                .outcome
+        );
+    }
+}
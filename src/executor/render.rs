@@ -0,0 +1,279 @@
+use super::Run;
+use crate::config::schedule::Zone;
+use crate::config::source::{DateFormat, Format, Offset, Source, Truncate};
+use crate::config::template::{Filter, Part, Placeholder, Subject, Template};
+use crate::config::value::Value;
+use chrono::{DateTime, NaiveDateTime, NaiveTime, SecondsFormat, TimeDelta, Utc};
+use chrono_tz::Tz;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::collections::HashMap;
+
+/// Everything but the unreserved characters of RFC 3986.
+pub const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Fields extracted from the responses of earlier pipeline steps, by step.
+pub type Steps = HashMap<String, HashMap<String, serde_json::Value>>;
+
+/// Resolves config values into what is sent for one run.
+pub struct Context<'a> {
+    run: &'a Run,
+    steps: Option<&'a Steps>,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(run: &'a Run) -> Self {
+        Self { run, steps: None }
+    }
+
+    pub fn with_steps(run: &'a Run, steps: &'a Steps) -> Self {
+        Self {
+            run,
+            steps: Some(steps),
+        }
+    }
+
+    fn step(&self, step: &str, field: &str) -> Option<&serde_json::Value> {
+        self.steps?
+            .get(step)?
+            .get(field)
+    }
+
+    /// Renders `source` as an RFC3339 date, or `None` when it has no value
+    /// yet, e.g. `last_execute_time` before the first run.
+    pub fn source(&self, source: &Source) -> Option<String> {
+        let date = match source {
+            Source::ExecuteDate => Some(self.run.scheduled_at),
+            Source::LastExecuteDate => self.run.last_scheduled_at,
+            Source::State(name) => {
+                return self
+                    .state(name)
+                    .and_then(text)
+            }
+        };
+
+        date.map(rfc3339)
+    }
+
+    /// Renders a date source in `format`, Unix times as numbers.
+    fn date(&self, source: &Source, format: &DateFormat) -> Option<serde_json::Value> {
+        let date = match source {
+            Source::ExecuteDate => self.run.scheduled_at,
+            Source::LastExecuteDate => self.run.last_scheduled_at?,
+            // Rejected when the config is loaded.
+            Source::State(_) => return None,
+        };
+
+        let date = shift(date.with_timezone(&format.timezone), &format.offset)?;
+        let date = match format.truncate {
+            Some(Truncate::Day) => wall_clock(
+                format.timezone,
+                date.date_naive()
+                    .and_time(NaiveTime::MIN),
+            )?,
+            None => date,
+        };
+
+        Some(match &format.format {
+            Format::Rfc3339 => {
+                serde_json::Value::String(date.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+            }
+            Format::Unix => serde_json::Value::from(date.timestamp()),
+            Format::UnixMs => serde_json::Value::from(date.timestamp_millis()),
+            Format::Strftime(pattern) => serde_json::Value::String(
+                date.format(pattern)
+                    .to_string(),
+            ),
+        })
+    }
+
+    fn state(&self, name: &str) -> Option<&serde_json::Value> {
+        self.run.state.get(name)
+    }
+
+    pub fn json(&self, value: &Value) -> serde_json::Value {
+        match value {
+            Value::Array(items) => serde_json::Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.json(item))
+                    .collect(),
+            ),
+            Value::Object(properties) => serde_json::Value::Object(
+                properties
+                    .iter()
+                    .map(|(key, value)| (key.clone(), self.json(value)))
+                    .collect(),
+            ),
+            Value::String(value) => serde_json::Value::String(value.clone()),
+            Value::Bool(value) => serde_json::Value::Bool(*value),
+            Value::Float(value) => serde_json::Number::from_f64(*value)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            Value::Integer(value) => serde_json::Value::from(*value),
+            Value::Null => serde_json::Value::Null,
+            Value::Source(Source::State(name)) => self
+                .state(name)
+                .cloned()
+                .unwrap_or(serde_json::Value::Null),
+            Value::Source(source) => self
+                .source(source)
+                .map(serde_json::Value::String)
+                .unwrap_or(serde_json::Value::Null),
+            Value::Step { step, field } => self
+                .step(step, field)
+                .cloned()
+                .unwrap_or(serde_json::Value::Null),
+            Value::Date(source, format) => self
+                .date(source, format)
+                .unwrap_or(serde_json::Value::Null),
+            Value::Template(template) => self
+                .template(template)
+                .map(serde_json::Value::String)
+                .unwrap_or(serde_json::Value::Null),
+        }
+    }
+
+    /// Renders a scalar as text, e.g. for a header. `None` means the value
+    /// should be left out.
+    pub fn text(&self, value: &Value) -> Option<String> {
+        match value {
+            Value::String(value) => Some(value.clone()),
+            Value::Bool(value) => Some(value.to_string()),
+            Value::Float(value) => Some(value.to_string()),
+            Value::Integer(value) => Some(value.to_string()),
+            Value::Source(source) => self.source(source),
+            Value::Step { step, field } => self
+                .step(step, field)
+                .and_then(text),
+            Value::Date(source, format) => self
+                .date(source, format)
+                .as_ref()
+                .and_then(text),
+            Value::Template(template) => self.template(template),
+            Value::Null | Value::Array(_) | Value::Object(_) => None,
+        }
+    }
+
+    /// Names what leaves `value` without text, e.g. `placeholder
+    /// state.customer`, or `None` when it is no scalar to begin with.
+    pub fn missing(&self, value: &Value) -> Option<String> {
+        match value {
+            Value::Source(source) | Value::Date(source, _) => Some(format!("source {source}")),
+            Value::Step { step, field } => Some(format!("field `{field}` of step `{step}`")),
+            Value::Template(template) => template
+                .placeholders()
+                .find(|placeholder| {
+                    self.placeholder(placeholder)
+                        .is_none()
+                })
+                .map(|placeholder| format!("placeholder {}", placeholder.subject)),
+            _ => None,
+        }
+    }
+
+    /// Renders a template, or `None` when a placeholder has no value and
+    /// no `default`.
+    pub fn template(&self, template: &Template) -> Option<String> {
+        let mut rendered = String::new();
+
+        for part in template.parts() {
+            match part {
+                Part::Literal(text) => rendered.push_str(text),
+                Part::Placeholder(placeholder) => {
+                    rendered.push_str(&self.placeholder(placeholder)?)
+                }
+            }
+        }
+
+        Some(rendered)
+    }
+
+    fn placeholder(&self, placeholder: &Placeholder) -> Option<String> {
+        let value = match &placeholder.subject {
+            Subject::Source(source) => self.source(source),
+            Subject::Step { step, field } => self
+                .step(step, field)
+                .and_then(text),
+        };
+
+        placeholder
+            .filters
+            .iter()
+            .fold(value, |value, filter| match filter {
+                Filter::Date(format) => value.and_then(|value| date(&value, format)),
+                Filter::Default(default) => value.or_else(|| Some(default.clone())),
+                Filter::Upper => value.map(|value| value.to_uppercase()),
+                Filter::Lower => value.map(|value| value.to_lowercase()),
+                Filter::UrlEncode => {
+                    value.map(|value| utf8_percent_encode(&value, COMPONENT).to_string())
+                }
+            })
+    }
+
+    /// Renders a query parameter as one text per repeated parameter, an
+    /// array, whether literal or extracted, giving one per item.
+    pub fn texts(&self, value: &Value) -> Vec<String> {
+        match self.json(value) {
+            serde_json::Value::Array(items) => items
+                .iter()
+                .filter_map(text)
+                .collect(),
+            value => text(&value)
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+/// Renders an extracted JSON value as text, strings without quotes.
+fn text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(value) => Some(value.clone()),
+        serde_json::Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+/// Moves `date` by whole days on its own calendar, then by the exact rest.
+fn shift(date: DateTime<Tz>, offset: &Offset) -> Option<DateTime<Tz>> {
+    let local = date
+        .naive_local()
+        .checked_add_signed(TimeDelta::try_days(offset.days)?)?;
+
+    wall_clock(date.timezone(), local)?.checked_add_signed(offset.time)
+}
+
+/// Resolves a wall-clock time the way cron schedules do by default: the
+/// first of a repeated time, and a skipped one as if the clock had not moved.
+fn wall_clock(timezone: Tz, local: NaiveDateTime) -> Option<DateTime<Tz>> {
+    Zone {
+        timezone,
+        ..Zone::default()
+    }
+    .resolve(local)
+    .first()
+    .map(|instant| instant.with_timezone(&timezone))
+}
+
+/// Formats an RFC3339 date for the `date` filter, the format was checked
+/// when the config was loaded.
+fn date(value: &str, format: &str) -> Option<String> {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(date) => Some(
+            date.format(format)
+                .to_string(),
+        ),
+        Err(err) => {
+            tracing::warn!(value, %err, "`date` filter needs an RFC3339 date, leaving the value out");
+            None
+        }
+    }
+}
+
+fn rfc3339(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
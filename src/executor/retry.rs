@@ -0,0 +1,199 @@
+use crate::config::retry::{Jitter, Retry};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use std::time::Duration;
+
+/// Yields the delays between attempts of one run.
+pub struct Backoff<'a> {
+    policy: Option<&'a Retry>,
+    attempts: u32,
+    previous: Duration,
+}
+
+impl<'a> Backoff<'a> {
+    pub fn new(policy: Option<&'a Retry>) -> Self {
+        Self {
+            policy,
+            attempts: 1,
+            previous: Duration::ZERO,
+        }
+    }
+
+    /// Returns how long to wait before the next attempt, or `None` when the
+    /// attempts are used up. A server-provided `retry_after` is waited out,
+    /// but never for longer than `max_delay`.
+    pub fn next_delay(&mut self, retry_after: Option<Duration>) -> Option<Duration> {
+        let policy = self.policy?;
+
+        if self.attempts >= policy.max_attempts {
+            return None;
+        }
+
+        let exponential = policy
+            .initial_delay
+            .as_secs_f64()
+            * policy
+                .multiplier
+                .powi(self.attempts as i32 - 1);
+        let exponential = exponential.min(policy.max_delay.as_secs_f64());
+
+        let mut rng = rand::rng();
+        let delay = match policy.jitter {
+            Jitter::None => exponential,
+            Jitter::Full => rng.random_range(0.0..=exponential),
+            Jitter::Decorrelated => {
+                let low = policy
+                    .initial_delay
+                    .as_secs_f64();
+                let high = (self.previous.as_secs_f64() * 3.0).max(low);
+
+                rng.random_range(low..=high)
+                    .min(policy.max_delay.as_secs_f64())
+            }
+        };
+
+        let delay = Duration::from_secs_f64(delay);
+        let delay = retry_after.map_or(delay, |retry_after| {
+            retry_after
+                .min(policy.max_delay)
+                .max(delay)
+        });
+
+        self.attempts += 1;
+        self.previous = delay;
+
+        Some(delay)
+    }
+
+    pub fn retries_status(&self, status: u16) -> bool {
+        self.policy
+            .is_some_and(|policy| {
+                policy
+                    .status_codes
+                    .contains(&status)
+            })
+    }
+}
+
+/// Parses a `Retry-After` header given either as seconds or as an HTTP date.
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+
+    Some(
+        (date.to_utc() - now)
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn policy(jitter: Jitter) -> Retry {
+        Retry {
+            max_attempts: 5,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter,
+            status_codes: vec![503],
+        }
+    }
+
+    #[test]
+    fn test_backoff_exponential() {
+        let policy = policy(Jitter::None);
+        let mut backoff = Backoff::new(Some(&policy));
+
+        let delays: Vec<Option<Duration>> = (0..5)
+            .map(|_| backoff.next_delay(None))
+            .collect();
+
+        assert_eq!(
+            vec![
+                Some(Duration::from_secs(1)),
+                Some(Duration::from_secs(2)),
+                Some(Duration::from_secs(4)),
+                Some(Duration::from_secs(5)),
+                None
+            ],
+            delays
+        );
+    }
+
+    #[test]
+    fn test_backoff_jitter_bounds() {
+        let full = policy(Jitter::Full);
+        let mut backoff = Backoff::new(Some(&full));
+        for max in [1, 2, 4, 5] {
+            let delay = backoff
+                .next_delay(None)
+                .unwrap();
+            assert!(delay <= Duration::from_secs(max), "{delay:?}");
+        }
+
+        let decorrelated = policy(Jitter::Decorrelated);
+        let mut backoff = Backoff::new(Some(&decorrelated));
+        for _ in 0..4 {
+            let delay = backoff
+                .next_delay(None)
+                .unwrap();
+            assert!(delay >= Duration::from_secs(1), "{delay:?}");
+            assert!(delay <= Duration::from_secs(5), "{delay:?}");
+        }
+    }
+
+    #[test]
+    fn test_backoff_honours_retry_after() {
+        let policy = policy(Jitter::None);
+        let mut backoff = Backoff::new(Some(&policy));
+
+        assert_eq!(
+            Some(Duration::from_secs(3)),
+            backoff.next_delay(Some(Duration::from_secs(3)))
+        );
+        assert_eq!(
+            Some(Duration::from_secs(2)),
+            backoff.next_delay(Some(Duration::from_millis(10)))
+        );
+        assert_eq!(
+            Some(Duration::from_secs(5)),
+            backoff.next_delay(Some(Duration::from_secs(3600)))
+        );
+    }
+
+    #[test]
+    fn test_backoff_without_policy() {
+        let mut backoff = Backoff::new(None);
+
+        assert_eq!(None, backoff.next_delay(None));
+        assert!(!backoff.retries_status(503));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = Utc
+            .with_ymd_and_hms(2026, 1, 1, 10, 0, 0)
+            .unwrap();
+
+        assert_eq!(
+            Some(Duration::from_secs(120)),
+            parse_retry_after("120", now)
+        );
+        assert_eq!(
+            Some(Duration::from_secs(90)),
+            parse_retry_after("Thu, 01 Jan 2026 10:01:30 GMT", now)
+        );
+        assert_eq!(
+            Some(Duration::ZERO),
+            parse_retry_after("Thu, 01 Jan 2026 09:00:00 GMT", now)
+        );
+        assert_eq!(None, parse_retry_after("later", now));
+    }
+}
@@ -0,0 +1,189 @@
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::Writer;
+
+/// Writes a rendered body as an XML document:
+///
+/// - the body is an object with a single key, the root element;
+/// - an object key becomes a child element, in key order;
+/// - a key starting with `@` becomes an attribute of the enclosing element,
+///   left out if null;
+/// - the `#text` key becomes the text of the enclosing element;
+/// - an array repeats its key's element once per item, e.g.
+///   `{"item": [1, 2]}` is `<item>1</item><item>2</item>`;
+/// - null is an empty element, other scalars their text.
+pub fn to_xml(value: &serde_json::Value) -> Result<Vec<u8>, String> {
+    let root = match value {
+        serde_json::Value::Object(map) if map.len() == 1 => map.iter().next(),
+        _ => None,
+    };
+
+    let Some((name, value)) = root.filter(|(name, value)| is_element(name) && !value.is_array())
+    else {
+        return Err(String::from(
+            "an xml body must be an object with a single root element",
+        ));
+    };
+
+    let mut writer = Writer::new(Vec::new());
+
+    write(
+        &mut writer,
+        Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)),
+    )?;
+    element(&mut writer, name, value)?;
+
+    Ok(writer.into_inner())
+}
+
+fn element(
+    writer: &mut Writer<Vec<u8>>,
+    name: &str,
+    value: &serde_json::Value,
+) -> Result<(), String> {
+    if !is_name(name) {
+        return Err(format!("`{name}` is not a valid XML name"));
+    }
+
+    let mut start = BytesStart::new(name);
+
+    let serde_json::Value::Object(map) = value else {
+        return match value {
+            serde_json::Value::Array(items) => items
+                .iter()
+                .try_for_each(|item| element(writer, name, item)),
+            serde_json::Value::Null => write(writer, Event::Empty(start)),
+            value => {
+                write(writer, Event::Start(start))?;
+                write(writer, Event::Text(BytesText::new(&text(value))))?;
+                write(writer, Event::End(BytesEnd::new(name)))
+            }
+        };
+    };
+
+    for (key, value) in map {
+        if let Some(attribute) = key.strip_prefix('@') {
+            if !is_name(attribute) {
+                return Err(format!("`{attribute}` is not a valid XML name"));
+            }
+
+            if !value.is_null() {
+                start.push_attribute((attribute, text(value).as_str()));
+            }
+        }
+    }
+
+    let mut content = map
+        .iter()
+        .filter(|(key, _)| !key.starts_with('@'))
+        .peekable();
+
+    if content.peek().is_none() {
+        return write(writer, Event::Empty(start));
+    }
+
+    write(writer, Event::Start(start))?;
+
+    for (key, value) in content {
+        match key.as_str() {
+            "#text" => write(writer, Event::Text(BytesText::new(&text(value))))?,
+            key => element(writer, key, value)?,
+        }
+    }
+
+    write(writer, Event::End(BytesEnd::new(name)))
+}
+
+fn write(writer: &mut Writer<Vec<u8>>, event: Event<'_>) -> Result<(), String> {
+    writer
+        .write_event(event)
+        .map_err(|err| format!("failed to write xml: {err}"))
+}
+
+fn text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+/// Whether a key names an element rather than an attribute or text.
+fn is_element(key: &str) -> bool {
+    !key.starts_with('@') && key != "#text"
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_' || first == ':')
+        && chars.all(|ch| ch.is_alphanumeric() || matches!(ch, '_' | ':' | '-' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn xml(value: serde_json::Value) -> Result<String, String> {
+        to_xml(&value).map(|xml| String::from_utf8(xml).unwrap())
+    }
+
+    #[test]
+    fn test_to_xml() {
+        assert_eq!(
+            Ok(String::from(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+                 <order id=\"42\" status=\"new\">\
+                 <item sku=\"a&amp;b\">2</item><item sku=\"c\"/>\
+                 <note>fish &amp; chips &lt;3</note>\
+                 <paid>false</paid>\
+                 <total currency=\"EUR\">9.5</total>\
+                 <voucher/>\
+                 </order>"
+            )),
+            xml(json!({
+                "order": {
+                    "@id": 42,
+                    "@status": "new",
+                    "@coupon": null,
+                    "item": [
+                        {"@sku": "a&b", "#text": 2},
+                        {"@sku": "c"},
+                    ],
+                    "note": "fish & chips <3",
+                    "paid": false,
+                    "total": {"@currency": "EUR", "#text": 9.5},
+                    "voucher": null,
+                }
+            }))
+        );
+        assert_eq!(
+            Ok(String::from(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?><ids><id>1</id><id>2</id><id>3</id></ids>"
+            )),
+            xml(json!({"ids": {"id": [1, [2, 3]]}}))
+        );
+    }
+
+    #[test]
+    fn test_to_xml_errors() {
+        let root = Err(String::from(
+            "an xml body must be an object with a single root element",
+        ));
+
+        assert_eq!(root, xml(json!({"a": 1, "b": 2})));
+        assert_eq!(root, xml(json!({"item": [1, 2]})));
+        assert_eq!(root, xml(json!({"@id": 1})));
+        assert_eq!(root, xml(json!([1])));
+        assert_eq!(
+            Err(String::from("`line item` is not a valid XML name")),
+            xml(json!({"order": {"line item": 1}}))
+        );
+        assert_eq!(
+            Err(String::from("`1st` is not a valid XML name")),
+            xml(json!({"order": {"@1st": true}}))
+        );
+    }
+}
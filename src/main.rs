@@ -1,8 +1,118 @@
+mod backfill;
 mod config;
+mod executor;
 mod scheduler;
+mod state;
 mod yaml;
 
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use std::error::Error;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Path to the tasks config
+    #[arg(short, long, default_value = "config/config.yaml")]
+    config: PathBuf,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Replays a task over a past date range, then exits
+    Backfill {
+        /// Name of the task to replay
+        #[arg(long)]
+        task: String,
+        /// Start of the range, the first run's last execute time (RFC3339)
+        #[arg(long)]
+        from: DateTime<Utc>,
+        /// End of the range, inclusive (RFC3339)
+        #[arg(long)]
+        to: DateTime<Utc>,
+        /// How many runs are sent at once
+        #[arg(long, default_value = "1")]
+        parallelism: NonZeroUsize,
+    },
+}
+
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .init();
+
+    let Err(err) = run(Cli::parse()).await else {
+        return ExitCode::SUCCESS;
+    };
+
+    eprintln!("{err}");
+
+    let mut source = err.source();
+    while let Some(err) = source {
+        eprintln!("  caused by: {err}");
+        source = err.source();
+    }
+
+    ExitCode::FAILURE
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let config = config::Config::from_file(&cli.config)?;
+    let store = state::open(config.store())?;
+    let executor = Arc::new(executor::Dispatcher::default());
+
+    let shutdown = CancellationToken::new();
+    tokio::spawn(shutdown_on_signal(shutdown.clone()));
+
+    match cli.command {
+        None => {
+            scheduler::Scheduler::new(config, executor, store)
+                .run(shutdown)
+                .await;
+        }
+        Some(Command::Backfill {
+            task,
+            from,
+            to,
+            parallelism,
+        }) => {
+            backfill::Backfill::new(config, &task, from, to, parallelism, executor, store)?
+                .run(shutdown)
+                .await?;
+        }
+    }
+
     Ok(())
 }
+
+async fn shutdown_on_signal(shutdown: CancellationToken) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate()).expect("failed to listen for SIGTERM");
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+
+    tracing::info!("shutting down");
+    shutdown.cancel();
+}
@@ -1 +1,1413 @@
-pub struct Scheduler {}
+use crate::config::schedule::Schedule;
+use crate::config::tasks::{self, Concurrency, Misfire, TriggerRule};
+use crate::config::Config;
+use crate::executor::{Executor, Outcome, Report, Run};
+use crate::state::{self, RunRecord, Store};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+/// Reads the wall clock, so tests can steer it along with tokio's paused
+/// time.
+type Clock = fn() -> DateTime<Utc>;
+
+pub struct Scheduler {
+    tasks: Vec<Arc<tasks::Task>>,
+    executor: Arc<dyn Executor>,
+    store: Arc<dyn Store>,
+    clock: Clock,
+}
+
+impl Scheduler {
+    pub fn new(config: Config, executor: Arc<dyn Executor>, store: Arc<dyn Store>) -> Self {
+        let tasks = config
+            .into_tasks()
+            .into_iter()
+            .map(Arc::new)
+            .collect();
+
+        Self {
+            tasks,
+            executor,
+            store,
+            clock: Utc::now,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_clock(self, clock: Clock) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Drives every task on its own timeline until `shutdown` is cancelled,
+    /// then waits up to `SHUTDOWN_GRACE` for the runs that are still in
+    /// flight and interrupts the rest.
+    pub async fn run(self, shutdown: CancellationToken) {
+        let runs = TaskTracker::new();
+        let interrupt = CancellationToken::new();
+        let mut timelines = JoinSet::new();
+        let started_at = (self.clock)();
+        let (completions, _) = broadcast::channel(COMPLETIONS_CAPACITY);
+
+        for task in self.tasks {
+            let downstream = !task
+                .options()
+                .depends_on
+                .is_empty();
+            let timeline = Timeline {
+                task,
+                executor: self.executor.clone(),
+                store: self.store.clone(),
+                runs: runs.clone(),
+                shutdown: shutdown.clone(),
+                interrupt: interrupt.clone(),
+                clock: self.clock,
+                started_at,
+                lane: Arc::default(),
+                last_scheduled_at: Arc::default(),
+                completions: completions.clone(),
+            };
+
+            if downstream {
+                // Subscribed before any run can complete.
+                timelines.spawn(timeline.follow(completions.subscribe()));
+            } else {
+                timelines.spawn(timeline.run());
+            }
+        }
+
+        shutdown.cancelled().await;
+        timelines.join_all().await;
+
+        runs.close();
+
+        if tokio::time::timeout(SHUTDOWN_GRACE, runs.wait())
+            .await
+            .is_err()
+        {
+            tracing::warn!(runs = runs.len(), "interrupting runs still in flight");
+            interrupt.cancel();
+            runs.wait().await;
+        }
+    }
+}
+
+/// How long shutting down waits for the runs in flight.
+const SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(30);
+
+struct Timeline {
+    task: Arc<tasks::Task>,
+    executor: Arc<dyn Executor>,
+    store: Arc<dyn Store>,
+    runs: TaskTracker,
+    shutdown: CancellationToken,
+    /// Cancels the runs in flight once shutting down took too long.
+    interrupt: CancellationToken,
+    clock: Clock,
+    started_at: DateTime<Utc>,
+    lane: Arc<Lane>,
+    /// When the latest run that actually ran was due.
+    last_scheduled_at: Arc<Mutex<Option<DateTime<Utc>>>>,
+    completions: broadcast::Sender<Completion>,
+}
+
+impl Timeline {
+    /// Loads the last recorded run and remembers when it was due.
+    async fn load_last_run(&self) -> Option<RunRecord> {
+        let name = String::from(self.task.name());
+
+        let last_run = match state::blocking(&self.store, move |store| store.last_run(&name)).await
+        {
+            Ok(last_run) => last_run,
+            Err(err) => {
+                tracing::error!(task = self.task.name(), %err, "failed to load last run");
+                None
+            }
+        };
+
+        *self
+            .last_scheduled_at
+            .lock()
+            .unwrap() = last_run
+            .as_ref()
+            .map(|run| run.scheduled_at);
+
+        last_run
+    }
+
+    async fn run(self) {
+        let last_run = self.load_last_run().await;
+
+        // Occurrences between the last recorded run and start-up were missed
+        // while the scheduler was down. Intervals anchored to start-up count
+        // them from the last run instead. A one-shot that has not succeeded
+        // yet was missed if its time has passed.
+        let missed_since = match (last_run, &self.task.options().schedule) {
+            (Some(last), Some(Schedule::At(_))) if last.outcome != Outcome::Success => {
+                Some(DateTime::<Utc>::MIN_UTC)
+            }
+            (Some(last), _) => Some(last.scheduled_at),
+            (None, Some(Schedule::At(_))) => Some(DateTime::<Utc>::MIN_UTC),
+            (None, _) => None,
+        };
+
+        if let Some(since) = missed_since {
+            if !self
+                .dispatch_due(since, self.started_at, since)
+                .await
+            {
+                return;
+            }
+        }
+
+        let mut after = self.started_at;
+
+        while let Some(scheduled_at) = self
+            .task
+            .next_run(after, self.started_at)
+        {
+            let delay = (scheduled_at - (self.clock)())
+                .to_std()
+                .unwrap_or_default();
+
+            tokio::select! {
+                _ = self.shutdown.cancelled() => return,
+                _ = tokio::time::sleep(delay) => {}
+            }
+
+            // More may have fallen due if the timeline woke late, e.g. after
+            // the host was suspended.
+            let until = (self.clock)().max(scheduled_at);
+
+            if !self
+                .dispatch_due(after, until, self.started_at)
+                .await
+            {
+                return;
+            }
+
+            after = until;
+        }
+
+        tracing::debug!(task = self.task.name(), "no runs left");
+    }
+
+    /// Runs the task whenever its upstream tasks complete as its trigger rule
+    /// asks. A run takes the latest execute date of the upstream runs that
+    /// triggered it.
+    async fn follow(self, mut completions: broadcast::Receiver<Completion>) {
+        let options = self.task.options();
+        let upstream_tasks: HashSet<&str> = options
+            .depends_on
+            .iter()
+            .map(String::as_str)
+            .collect();
+        self.load_last_run().await;
+        // The latest run of every upstream task since this one was triggered.
+        let mut upstream: HashMap<String, Completion> = HashMap::new();
+        // Set once `any_success` fired, until the rest of the round reported.
+        let mut fired = false;
+
+        loop {
+            let completion = tokio::select! {
+                _ = self.shutdown.cancelled() => return,
+                completion = completions.recv() => completion,
+            };
+
+            let completion = match completion {
+                Ok(completion) => completion,
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!(
+                        task = self.task.name(),
+                        missed,
+                        "missed upstream completions"
+                    );
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+
+            if !upstream_tasks.contains(completion.task.as_str()) {
+                continue;
+            }
+
+            upstream.insert(completion.task.clone(), completion);
+
+            let done = upstream.len() == upstream_tasks.len();
+
+            if fired {
+                if done {
+                    upstream.clear();
+                    fired = false;
+                }
+                continue;
+            }
+
+            let succeeded = upstream
+                .values()
+                .filter(|completion| completion.succeeded)
+                .count();
+
+            let trigger = match options.trigger_rule {
+                TriggerRule::AllSuccess => done.then_some(succeeded == upstream.len()),
+                TriggerRule::AllDone => done.then_some(true),
+                TriggerRule::AnySuccess if succeeded > 0 => Some(true),
+                TriggerRule::AnySuccess => done.then_some(false),
+            };
+
+            let Some(trigger) = trigger else {
+                continue;
+            };
+
+            let scheduled_at = upstream
+                .values()
+                .map(|completion| completion.scheduled_at)
+                .max()
+                .unwrap_or_else(self.clock);
+
+            if done {
+                upstream.clear();
+            } else {
+                fired = true;
+            }
+
+            if trigger {
+                self.dispatch(scheduled_at);
+            } else {
+                self.spawn(
+                    scheduled_at,
+                    Admission::Skipped("upstream tasks failed"),
+                    CancellationToken::new(),
+                );
+            }
+        }
+    }
+
+    /// Yields the occurrences in `(after, until]`, oldest first.
+    fn occurrences(
+        &self,
+        after: DateTime<Utc>,
+        until: DateTime<Utc>,
+        origin: DateTime<Utc>,
+    ) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+        std::iter::successors(
+            self.task
+                .next_run(after, origin),
+            move |previous| {
+                self.task
+                    .next_run(*previous, origin)
+            },
+        )
+        .take_while(move |next| *next <= until)
+    }
+
+    /// Dispatches the occurrences in `(after, until]`, applying the misfire
+    /// policy to the ones past the grace window. Catch-up runs go one at a
+    /// time, oldest first, each seeing the previous one as its last run.
+    /// `skip` and `latest` jump over the missed occurrences rather than
+    /// walking through them. Returns `false` once shut down.
+    async fn dispatch_due(
+        &self,
+        after: DateTime<Utc>,
+        until: DateTime<Utc>,
+        origin: DateTime<Utc>,
+    ) -> bool {
+        let options = self.task.options();
+        let grace = TimeDelta::from_std(options.misfire_grace).unwrap_or(TimeDelta::MAX);
+        let deadline = (self.clock)()
+            .checked_sub_signed(grace)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let missed_until = deadline.min(until);
+
+        let first_missed = self
+            .task
+            .next_run(after, origin)
+            .filter(|first| *first <= missed_until);
+
+        if let Some(first) = first_missed {
+            match options.misfire {
+                Misfire::All => {
+                    for scheduled_at in self.occurrences(after, missed_until, origin) {
+                        if !self
+                            .catch_up(scheduled_at)
+                            .await
+                        {
+                            return false;
+                        }
+                    }
+                }
+                Misfire::Latest => {
+                    let latest = self
+                        .task
+                        .last_run_until(missed_until, origin)
+                        .filter(|latest| *latest > first)
+                        .unwrap_or(first);
+
+                    if latest > first {
+                        tracing::warn!(
+                            task = self.task.name(),
+                            from = %first,
+                            before = %latest,
+                            "skipped missed runs"
+                        );
+                    }
+
+                    if !self.catch_up(latest).await {
+                        return false;
+                    }
+                }
+                Misfire::Skip => {
+                    tracing::warn!(
+                        task = self.task.name(),
+                        from = %first,
+                        until = %missed_until,
+                        "skipped missed runs"
+                    );
+                }
+            }
+        }
+
+        for scheduled_at in self.occurrences(after.max(missed_until), until, origin) {
+            self.dispatch(scheduled_at);
+        }
+
+        true
+    }
+
+    /// Runs a missed occurrence and waits for it. Returns `false` once shut
+    /// down.
+    async fn catch_up(&self, scheduled_at: DateTime<Utc>) -> bool {
+        let run = self.dispatch(scheduled_at);
+
+        tokio::select! {
+            _ = self.shutdown.cancelled() => false,
+            _ = run => true,
+        }
+    }
+
+    /// Decides whether a run starts now, waits for the lane, or is skipped.
+    fn admit(&self, cancel: &CancellationToken) -> Admission {
+        let options = self.task.options();
+
+        if options.concurrency == Concurrency::Allow {
+            return Admission::Now(None);
+        }
+
+        if let Ok(permit) = self
+            .lane
+            .permit
+            .clone()
+            .try_acquire_owned()
+        {
+            *self
+                .lane
+                .current
+                .lock()
+                .unwrap() = cancel.clone();
+            return Admission::Now(Some(permit));
+        }
+
+        match options.concurrency {
+            Concurrency::Allow | Concurrency::Forbid => {
+                Admission::Skipped("previous run still in flight")
+            }
+            Concurrency::Replace => {
+                let previous = std::mem::replace(
+                    &mut *self
+                        .lane
+                        .current
+                        .lock()
+                        .unwrap(),
+                    cancel.clone(),
+                );
+                previous.cancel();
+                self.lane
+                    .waiting
+                    .fetch_add(1, Ordering::SeqCst);
+                Admission::Wait
+            }
+            Concurrency::Queue => {
+                if self
+                    .lane
+                    .waiting
+                    .load(Ordering::SeqCst)
+                    >= options.queue_limit
+                {
+                    return Admission::Skipped("queue is full");
+                }
+
+                self.lane
+                    .waiting
+                    .fetch_add(1, Ordering::SeqCst);
+                Admission::Wait
+            }
+        }
+    }
+
+    fn dispatch(&self, scheduled_at: DateTime<Utc>) -> JoinHandle<()> {
+        let cancel = CancellationToken::new();
+        let admission = self.admit(&cancel);
+
+        self.spawn(scheduled_at, admission, cancel)
+    }
+
+    fn spawn(
+        &self,
+        scheduled_at: DateTime<Utc>,
+        admission: Admission,
+        cancel: CancellationToken,
+    ) -> JoinHandle<()> {
+        let task = self.task.clone();
+        let executor = self.executor.clone();
+        let store = self.store.clone();
+        let lane = self.lane.clone();
+        let last_scheduled_at = self.last_scheduled_at.clone();
+        let shutdown = self.shutdown.clone();
+        let interrupt = self.interrupt.clone();
+        let clock = self.clock;
+        let completions = self.completions.clone();
+
+        self.runs.spawn(async move {
+            let permit = match admission {
+                Admission::Now(permit) => Ok(permit),
+                Admission::Skipped(reason) => Err(Outcome::Skipped(String::from(reason))),
+                Admission::Wait => {
+                    let permit = tokio::select! {
+                        biased;
+                        _ = shutdown.cancelled() => Err(Outcome::Skipped(String::from("shutting down"))),
+                        _ = cancel.cancelled() => Err(Outcome::Replaced),
+                        permit = lane.permit.clone().acquire_owned() => Ok(permit.ok()),
+                    };
+                    lane.waiting.fetch_sub(1, Ordering::SeqCst);
+
+                    permit
+                }
+            };
+
+            let started_at = clock();
+            let report = match permit {
+                Ok(permit) => {
+                    tracing::info!(task = task.name(), %scheduled_at, "run started");
+
+                    // Read when the run starts so a queued run sees the one
+                    // before it and what it extracted.
+                    let mut run = Run {
+                        scheduled_at,
+                        last_scheduled_at: *last_scheduled_at
+                            .lock()
+                            .unwrap(),
+                        state: task.state_defaults(),
+                    };
+                    let name = String::from(task.name());
+                    match state::blocking(&store, move |store| store.state(&name)).await {
+                        Ok(state) => run.state.extend(state),
+                        Err(err) => {
+                            tracing::error!(task = task.name(), %err, "failed to load state")
+                        }
+                    }
+
+                    // Dropping the executor future cancels the request in flight.
+                    let report = tokio::select! {
+                        _ = cancel.cancelled() => Report {
+                            outcome: Outcome::Replaced,
+                            attempts: vec![],
+                            pages: vec![],
+                            state: state::Values::new(),
+                        },
+                        _ = interrupt.cancelled() => Report {
+                            outcome: Outcome::Failure(String::from("interrupted by shutdown")),
+                            attempts: vec![],
+                            pages: vec![],
+                            state: state::Values::new(),
+                        },
+                        report = executor.execute(&task, &run) => report,
+                    };
+                    drop(permit);
+
+                    report
+                }
+                Err(outcome) => Report {
+                    outcome,
+                    attempts: vec![],
+                    pages: vec![],
+                    state: state::Values::new(),
+                },
+            };
+            let finished_at = clock();
+
+            match &report.outcome {
+                Outcome::Success => tracing::info!(task = task.name(), "run succeeded"),
+                Outcome::Failure(reason) => {
+                    tracing::warn!(task = task.name(), %reason, "run failed")
+                }
+                Outcome::Skipped(reason) => {
+                    tracing::warn!(task = task.name(), %scheduled_at, %reason, "run skipped")
+                }
+                Outcome::Replaced => tracing::warn!(task = task.name(), "run replaced by a newer one"),
+            }
+
+            let record = RunRecord {
+                scheduled_at,
+                started_at,
+                finished_at,
+                outcome: report.outcome,
+                attempts: report.attempts,
+                pages: report.pages,
+            };
+
+            let succeeded = record.outcome == Outcome::Success;
+
+            if record.ran() {
+                let mut last_scheduled_at = last_scheduled_at
+                    .lock()
+                    .unwrap();
+                *last_scheduled_at = (*last_scheduled_at).max(Some(scheduled_at));
+            }
+            let name = String::from(task.name());
+            let values = report.state;
+
+            let recorded =
+                state::blocking(&store, move |store| store.record_run(&name, &record, &values))
+                    .await;
+
+            if let Err(err) = recorded {
+                tracing::error!(task = task.name(), %err, "failed to record run");
+            }
+
+            // Nobody listening only means no task depends on this one.
+            let _ = completions.send(Completion {
+                task: String::from(task.name()),
+                scheduled_at,
+                succeeded,
+            });
+        })
+    }
+}
+
+/// How many completions a downstream task may fall behind on.
+const COMPLETIONS_CAPACITY: usize = 1024;
+
+/// A finished run, announced to the tasks that depend on it.
+#[derive(Debug, Clone)]
+struct Completion {
+    task: String,
+    scheduled_at: DateTime<Utc>,
+    succeeded: bool,
+}
+
+/// Serialises the runs of a task under every concurrency policy but `allow`.
+struct Lane {
+    permit: Arc<Semaphore>,
+    /// Runs waiting for the permit.
+    waiting: AtomicUsize,
+    /// Cancels the run holding the permit.
+    current: Mutex<CancellationToken>,
+}
+
+impl Default for Lane {
+    fn default() -> Self {
+        Self {
+            permit: Arc::new(Semaphore::new(1)),
+            waiting: AtomicUsize::new(0),
+            current: Mutex::new(CancellationToken::new()),
+        }
+    }
+}
+
+enum Admission {
+    /// Runs right away, holding the lane if the policy needs one.
+    Now(Option<OwnedSemaphorePermit>),
+    /// Runs once the lane is free.
+    Wait,
+    Skipped(&'static str),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::json::JsonStore;
+    use crate::state::Error;
+    use async_trait::async_trait;
+    use chrono::{Datelike, DurationRound, TimeZone};
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[derive(Default)]
+    struct Recorder {
+        runs: Mutex<Vec<(String, Run)>>,
+        delay: Duration,
+        failing: Option<&'static str>,
+        extracts: state::Values,
+    }
+
+    #[async_trait]
+    impl Executor for Recorder {
+        async fn execute(&self, task: &tasks::Task, run: &Run) -> Report {
+            self.runs
+                .lock()
+                .unwrap()
+                .push((String::from(task.name()), run.clone()));
+            tokio::time::sleep(self.delay).await;
+
+            let outcome = if Some(task.name()) == self.failing {
+                Outcome::Failure(String::from("unexpected status 500"))
+            } else {
+                Outcome::Success
+            };
+
+            Report {
+                outcome,
+                attempts: vec![],
+                pages: vec![],
+                state: self.extracts.clone(),
+            }
+        }
+    }
+
+    /// The wall clock as it moves with tokio's paused time, from the time
+    /// the test first reads it.
+    fn clock() -> DateTime<Utc> {
+        thread_local! {
+            static ORIGIN: (DateTime<Utc>, tokio::time::Instant) =
+                (Utc::now(), tokio::time::Instant::now());
+        }
+
+        ORIGIN.with(|(now, origin)| *now + TimeDelta::from_std(origin.elapsed()).unwrap())
+    }
+
+    /// Runs the tasks of `config` on paused time for `duration`, then shuts
+    /// down and returns the runs `recorder` saw, by task.
+    async fn schedule(
+        config: &str,
+        store: Arc<dyn Store>,
+        recorder: Recorder,
+        duration: Duration,
+    ) -> Vec<(String, Run)> {
+        let config: Config = config.parse().unwrap();
+        let recorder = Arc::new(recorder);
+        let scheduler = Scheduler::new(config, recorder.clone(), store).with_clock(clock);
+        let shutdown = CancellationToken::new();
+
+        let handle = tokio::spawn(scheduler.run(shutdown.clone()));
+        tokio::time::sleep(duration).await;
+        shutdown.cancel();
+        handle.await.unwrap();
+
+        let runs = recorder
+            .runs
+            .lock()
+            .unwrap()
+            .clone();
+        runs
+    }
+
+    fn runs_of(runs: &[(String, Run)], name: &str) -> Vec<Run> {
+        runs.iter()
+            .filter(|(task, _)| task == name)
+            .map(|(_, run)| run.clone())
+            .collect()
+    }
+
+    fn json_store() -> (TempDir, Arc<JsonStore>) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(JsonStore::open(dir.path().join("state.json")).unwrap());
+
+        (dir, store)
+    }
+
+    /// Records a run of `task` as if an earlier scheduler had.
+    fn record(
+        store: &dyn Store,
+        task: &str,
+        scheduled_at: DateTime<Utc>,
+        outcome: Outcome,
+        values: &state::Values,
+    ) {
+        store
+            .record_run(
+                task,
+                &RunRecord {
+                    scheduled_at,
+                    started_at: scheduled_at,
+                    finished_at: scheduled_at,
+                    outcome,
+                    attempts: vec![],
+                    pages: vec![],
+                },
+                values,
+            )
+            .unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_dispatches_due_runs_until_shutdown() {
+        let (_dir, store) = json_store();
+
+        let runs = schedule(
+            "
+          tasks:
+            - type: http
+              name: every_second
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"* * * * * *\"
+            - type: http
+              name: never_due
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"0 0 0 1 1 * 2020\"",
+            store.clone(),
+            Recorder::default(),
+            Duration::from_millis(2500),
+        )
+        .await;
+
+        assert!(runs.len() >= 2, "{runs:?}");
+        assert!(runs
+            .iter()
+            .all(|(name, _)| name == "every_second"));
+        assert_eq!(None, runs[0].1.last_scheduled_at);
+        assert_eq!(Some(runs[0].1.scheduled_at), runs[1].1.last_scheduled_at);
+        assert_eq!(
+            chrono::Duration::seconds(1),
+            runs[1].1.scheduled_at - runs[0].1.scheduled_at
+        );
+
+        let last_run = store
+            .last_run("every_second")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            runs.last()
+                .unwrap()
+                .1
+                .scheduled_at,
+            last_run.scheduled_at
+        );
+        assert_eq!(Outcome::Success, last_run.outcome);
+        assert!(last_run.started_at >= last_run.scheduled_at);
+        assert!(last_run.finished_at >= last_run.started_at);
+        assert_eq!(
+            None,
+            store
+                .last_run("never_due")
+                .unwrap()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_resumes_last_execute_date_from_store() {
+        let (_dir, store) = json_store();
+        let previous = clock() - chrono::Duration::hours(1);
+        record(
+            store.as_ref(),
+            "every_second",
+            previous,
+            Outcome::Success,
+            &state::Values::new(),
+        );
+
+        let runs = schedule(
+            "
+          tasks:
+            - type: http
+              name: every_second
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"* * * * * *\"",
+            store,
+            Recorder::default(),
+            Duration::from_millis(1500),
+        )
+        .await;
+
+        assert_eq!(Some(previous), runs[0].1.last_scheduled_at);
+    }
+
+    async fn catch_up(misfire: &str) -> (DateTime<Utc>, Vec<Run>) {
+        let now = clock();
+        let previous = now
+            .duration_trunc(TimeDelta::minutes(10))
+            .unwrap()
+            - TimeDelta::minutes(30);
+
+        let (_dir, store) = json_store();
+        record(
+            store.as_ref(),
+            "every_ten_minutes",
+            previous,
+            Outcome::Success,
+            &state::Values::new(),
+        );
+
+        let runs = schedule(
+            &format!(
+                "
+          tasks:
+            - type: http
+              name: every_ten_minutes
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"*/10 * * * *\"
+              misfire: {misfire}
+              misfire_grace: 1ms"
+            ),
+            store,
+            Recorder::default(),
+            Duration::from_millis(300),
+        )
+        .await
+        .into_iter()
+        .map(|(_, run)| run)
+        .filter(|run| run.scheduled_at <= now)
+        .collect();
+
+        (previous, runs)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_catches_up_all_missed_runs() {
+        let (previous, runs) = catch_up("all").await;
+
+        let expected: Vec<Run> = (1..=3)
+            .map(|i| Run {
+                scheduled_at: previous + TimeDelta::minutes(10 * i),
+                last_scheduled_at: Some(previous + TimeDelta::minutes(10 * (i - 1))),
+                state: state::Values::new(),
+            })
+            .collect();
+
+        assert_eq!(expected, runs);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_catches_up_latest_missed_run() {
+        let (previous, runs) = catch_up("latest").await;
+
+        assert_eq!(
+            vec![Run {
+                scheduled_at: previous + TimeDelta::minutes(30),
+                last_scheduled_at: Some(previous),
+                state: state::Values::new(),
+            }],
+            runs
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_skips_missed_runs() {
+        let (_, runs) = catch_up("skip").await;
+
+        assert_eq!(Vec::<Run>::new(), runs);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_jumps_over_years_of_missed_runs() {
+        let previous = Utc
+            .with_ymd_and_hms(2016, 1, 1, 0, 0, 0)
+            .unwrap();
+        let (_dir, store) = json_store();
+        for task in ["every_second", "yearly"] {
+            record(
+                store.as_ref(),
+                task,
+                previous,
+                Outcome::Success,
+                &state::Values::new(),
+            );
+        }
+        let started_at = clock();
+
+        let runs = schedule(
+            "
+          tasks:
+            - type: http
+              name: every_second
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"* * * * * *\"
+              misfire: latest
+              misfire_grace: 1s
+            - type: http
+              name: yearly
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"@yearly\"
+              misfire_grace: 300000y",
+            store,
+            Recorder::default(),
+            Duration::from_millis(300),
+        )
+        .await;
+
+        // The latest missed second, not the ten years of seconds before it.
+        let caught_up = &runs_of(&runs, "every_second")[0];
+        assert_eq!(Some(previous), caught_up.last_scheduled_at);
+        assert!(started_at - caught_up.scheduled_at < TimeDelta::seconds(3));
+
+        // A grace reaching past the earliest date keeps every missed run on time.
+        assert_eq!(
+            (started_at.year() - 2016) as usize,
+            runs_of(&runs, "yearly").len()
+        );
+    }
+
+    /// Starts a one-shot due an hour ago, after a run of it ending in
+    /// `previous` if given.
+    async fn one_shot(misfire: &str, previous: Option<Outcome>) -> (DateTime<Utc>, Vec<Run>) {
+        let at = clock() - TimeDelta::hours(1);
+        let (_dir, store) = json_store();
+        if let Some(outcome) = previous {
+            record(store.as_ref(), "once", at, outcome, &state::Values::new());
+        }
+
+        let runs = schedule(
+            &format!(
+                "
+          tasks:
+            - type: http
+              name: once
+              method: GET
+              url: http://localhost:3030/load
+              at: {}
+              misfire: {misfire}",
+                at.to_rfc3339()
+            ),
+            store,
+            Recorder::default(),
+            Duration::from_millis(300),
+        )
+        .await;
+
+        (at, runs_of(&runs, "once"))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_catches_up_missed_one_shot() {
+        let (at, runs) = one_shot("all", None).await;
+
+        assert_eq!(
+            vec![Run {
+                scheduled_at: at,
+                last_scheduled_at: None,
+                state: state::Values::new(),
+            }],
+            runs
+        );
+
+        let (_, runs) = one_shot("skip", None).await;
+
+        assert_eq!(Vec::<Run>::new(), runs);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_retries_failed_one_shot_after_restart() {
+        let (at, runs) = one_shot("all", Some(Outcome::Failure(String::from("timeout")))).await;
+
+        assert_eq!(
+            vec![Run {
+                scheduled_at: at,
+                last_scheduled_at: Some(at),
+                state: state::Values::new(),
+            }],
+            runs
+        );
+
+        let (_, runs) = one_shot("all", Some(Outcome::Success)).await;
+
+        assert_eq!(Vec::<Run>::new(), runs);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_interrupts_runs_outlasting_shutdown_grace() {
+        let at = clock() - TimeDelta::hours(1);
+        let (_dir, store) = json_store();
+        let started = tokio::time::Instant::now();
+
+        schedule(
+            &format!(
+                "
+          tasks:
+            - type: http
+              name: once
+              method: GET
+              url: http://localhost:3030/load
+              at: {}
+              misfire: all",
+                at.to_rfc3339()
+            ),
+            store.clone(),
+            Recorder {
+                delay: Duration::from_secs(3600),
+                ..Recorder::default()
+            },
+            Duration::from_millis(100),
+        )
+        .await;
+
+        assert_eq!(
+            Duration::from_millis(100) + SHUTDOWN_GRACE,
+            started.elapsed()
+        );
+        assert_eq!(
+            Some(Outcome::Failure(String::from("interrupted by shutdown"))),
+            store
+                .last_run("once")
+                .unwrap()
+                .map(|run| run.outcome)
+        );
+    }
+
+    #[derive(Default)]
+    struct History {
+        records: Mutex<Vec<(String, RunRecord)>>,
+    }
+
+    impl History {
+        fn outcomes(&self, name: &str) -> Vec<Outcome> {
+            self.records
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(task, _)| task == name)
+                .map(|(_, record)| record.outcome.clone())
+                .collect()
+        }
+    }
+
+    impl Store for History {
+        fn last_run(&self, _task: &str) -> Result<Option<RunRecord>, Error> {
+            Ok(None)
+        }
+
+        fn record_run(
+            &self,
+            task: &str,
+            record: &RunRecord,
+            _values: &state::Values,
+        ) -> Result<(), Error> {
+            self.records
+                .lock()
+                .unwrap()
+                .push((String::from(task), record.clone()));
+            Ok(())
+        }
+
+        fn backfill_progress(&self, _id: &str) -> Result<Option<DateTime<Utc>>, Error> {
+            Ok(None)
+        }
+
+        fn record_backfill_progress(
+            &self,
+            _id: &str,
+            _done_until: DateTime<Utc>,
+        ) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn state(&self, _task: &str) -> Result<state::Values, Error> {
+            Ok(state::Values::new())
+        }
+    }
+
+    /// Runs a per-second task whose runs take `delay` for `duration`.
+    async fn overlap(
+        concurrency: &str,
+        delay: Duration,
+        duration: Duration,
+    ) -> (Vec<Run>, Vec<RunRecord>) {
+        let store = Arc::new(History::default());
+
+        let runs = schedule(
+            &format!(
+                "
+          tasks:
+            - type: http
+              name: every_second
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"* * * * * *\"
+              concurrency: {concurrency}
+              queue_limit: 1"
+            ),
+            store.clone(),
+            Recorder {
+                delay,
+                ..Recorder::default()
+            },
+            duration,
+        )
+        .await;
+
+        let records = store
+            .records
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, record)| record.clone())
+            .collect();
+
+        (runs_of(&runs, "every_second"), records)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_forbids_overlapping_runs() {
+        let (runs, records) = overlap(
+            "forbid",
+            Duration::from_millis(1500),
+            Duration::from_millis(3500),
+        )
+        .await;
+
+        assert!(records
+            .iter()
+            .any(|record| record.outcome
+                == Outcome::Skipped(String::from("previous run still in flight"))));
+        assert!(runs.len() < records.len(), "{records:?}");
+
+        // A skipped run leaves the window alone: each run follows on from
+        // the one before it that ran.
+        assert!(runs.len() > 1, "{runs:?}");
+        for pair in runs.windows(2) {
+            assert_eq!(Some(pair[0].scheduled_at), pair[1].last_scheduled_at);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_replaces_overlapping_runs() {
+        let (runs, records) = overlap(
+            "replace",
+            Duration::from_millis(1500),
+            Duration::from_millis(3500),
+        )
+        .await;
+
+        let replaced: Vec<_> = records
+            .iter()
+            .filter(|record| record.outcome == Outcome::Replaced)
+            .map(|record| record.scheduled_at)
+            .collect();
+
+        assert!(!replaced.is_empty(), "{records:?}");
+        assert!(records
+            .iter()
+            .all(|record| record.outcome != Outcome::Success
+                || !replaced.contains(&record.scheduled_at)));
+        assert!(runs.len() >= 2, "{runs:?}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_queues_overlapping_runs() {
+        let (runs, records) = overlap(
+            "queue",
+            Duration::from_millis(2500),
+            Duration::from_millis(4000),
+        )
+        .await;
+
+        assert!(records
+            .iter()
+            .any(|record| record.outcome == Outcome::Skipped(String::from("queue is full"))));
+        assert!(records
+            .iter()
+            .all(|record| record.outcome != Outcome::Replaced));
+        assert_eq!(
+            Some(runs[0].scheduled_at),
+            runs[1].last_scheduled_at,
+            "{runs:?}"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_triggers_downstream_tasks() {
+        let runs = schedule(
+            "
+          tasks:
+            - type: http
+              name: load_data
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"* * * * * *\"
+            - type: http
+              name: transform
+              method: POST
+              url: http://localhost:3030/transform
+              depends_on: [load_data]
+            - type: http
+              name: push_data
+              method: POST
+              url: http://localhost:3030/push
+              depends_on: [transform]",
+            Arc::new(History::default()),
+            Recorder::default(),
+            Duration::from_millis(2500),
+        )
+        .await;
+
+        let load_data = runs_of(&runs, "load_data");
+        let transform = runs_of(&runs, "transform");
+        let push_data = runs_of(&runs, "push_data");
+
+        assert!(transform.len() >= 2, "{runs:?}");
+        assert_eq!(load_data[..transform.len()], transform[..]);
+        assert_eq!(transform[..push_data.len()], push_data[..]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_applies_trigger_rules() {
+        let store = Arc::new(History::default());
+
+        schedule(
+            "
+          tasks:
+            - type: http
+              name: load_data
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"* * * * * *\"
+            - type: http
+              name: load_more_data
+              method: GET
+              url: http://localhost:3030/load_more
+              schedule: \"* * * * * *\"
+            - type: http
+              name: all_success
+              method: POST
+              url: http://localhost:3030/transform
+              depends_on: [load_data, load_more_data]
+            - type: http
+              name: all_done
+              method: POST
+              url: http://localhost:3030/transform
+              depends_on: [load_data, load_more_data]
+              trigger_rule: all_done
+            - type: http
+              name: any_success
+              method: POST
+              url: http://localhost:3030/transform
+              depends_on: [load_data, load_more_data]
+              trigger_rule: any_success",
+            store.clone(),
+            Recorder {
+                failing: Some("load_more_data"),
+                ..Recorder::default()
+            },
+            Duration::from_millis(1500),
+        )
+        .await;
+
+        assert!(store
+            .outcomes("all_success")
+            .iter()
+            .all(|outcome| *outcome == Outcome::Skipped(String::from("upstream tasks failed"))));
+        assert!(!store
+            .outcomes("all_success")
+            .is_empty());
+        assert!(store
+            .outcomes("all_done")
+            .contains(&Outcome::Success));
+        assert!(store
+            .outcomes("any_success")
+            .contains(&Outcome::Success));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_triggers_any_success_once_per_round() {
+        let runs = schedule(
+            "
+          tasks:
+            - type: http
+              name: load_data
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"* * * * * *\"
+            - type: http
+              name: load_more_data
+              method: GET
+              url: http://localhost:3030/load_more
+              schedule: \"* * * * * *\"
+            - type: http
+              name: any_success
+              method: POST
+              url: http://localhost:3030/transform
+              depends_on: [load_data, load_more_data]
+              trigger_rule: any_success",
+            Arc::new(History::default()),
+            Recorder::default(),
+            Duration::from_millis(2500),
+        )
+        .await;
+
+        let runs: Vec<DateTime<Utc>> = runs_of(&runs, "any_success")
+            .iter()
+            .map(|run| run.scheduled_at)
+            .collect();
+        let rounds: HashSet<&DateTime<Utc>> = runs.iter().collect();
+
+        assert!(!runs.is_empty());
+        assert_eq!(rounds.len(), runs.len(), "{runs:?}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scheduler_passes_state_between_runs() {
+        let (_dir, store) = json_store();
+        record(
+            store.as_ref(),
+            "every_second",
+            clock() - TimeDelta::minutes(1),
+            Outcome::Success,
+            &state::Values::from([(String::from("cursor"), serde_json::json!("page-2"))]),
+        );
+
+        let runs = schedule(
+            "
+          tasks:
+            - type: http
+              name: every_second
+              method: GET
+              url: http://localhost:3030/load
+              schedule: \"* * * * * *\"
+              misfire_grace: 500ms
+              extract:
+                cursor: /next_page_token
+                max_id:
+                  path: /max_id
+                  default: 0",
+            store.clone(),
+            Recorder {
+                extracts: state::Values::from([(
+                    String::from("cursor"),
+                    serde_json::json!("page-3"),
+                )]),
+                ..Recorder::default()
+            },
+            Duration::from_millis(2500),
+        )
+        .await;
+
+        assert_eq!(
+            state::Values::from([
+                (String::from("cursor"), serde_json::json!("page-2")),
+                (String::from("max_id"), serde_json::json!(0)),
+            ]),
+            runs[0].1.state
+        );
+        assert_eq!(
+            Some(&serde_json::json!("page-3")),
+            runs[1].1.state.get("cursor")
+        );
+        assert_eq!(
+            Some(&serde_json::json!("page-3")),
+            store
+                .state("every_second")
+                .unwrap()
+                .get("cursor")
+        );
+    }
+}
@@ -0,0 +1,19 @@
+use super::RunRecord;
+use crate::executor::Outcome;
+use chrono::{TimeDelta, TimeZone, Utc};
+
+/// A run scheduled at `minute` past 10:00 on 2026-01-01.
+pub fn run(minute: u32, outcome: Outcome) -> RunRecord {
+    let scheduled_at = Utc
+        .with_ymd_and_hms(2026, 1, 1, 10, minute, 0)
+        .unwrap();
+
+    RunRecord {
+        scheduled_at,
+        started_at: scheduled_at + TimeDelta::milliseconds(5),
+        finished_at: scheduled_at + TimeDelta::milliseconds(250),
+        outcome,
+        attempts: vec![],
+        pages: vec![],
+    }
+}
@@ -0,0 +1,404 @@
+use super::{Result, RunRecord, Store, Values, HISTORY_LIMIT};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Keeps the whole state in one JSON file.
+///
+/// Every change rewrites the file through a synced temporary file that is
+/// renamed over the old one, so a crash leaves either the previous or the new
+/// state on disk, never a torn write.
+pub struct JsonStore {
+    path: PathBuf,
+    document: Mutex<Document>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct Document {
+    tasks: BTreeMap<String, TaskDocument>,
+    #[serde(default)]
+    backfills: BTreeMap<String, DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct TaskDocument {
+    /// Latest `scheduled_at` first, runs due at the same time newest first.
+    runs: VecDeque<RunRecord>,
+    #[serde(default, skip_serializing_if = "Values::is_empty")]
+    state: Values,
+}
+
+impl JsonStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let document = match fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Document::default(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Self {
+            path,
+            document: Mutex::new(document),
+        })
+    }
+
+    /// Applies `change` to a copy of the document and keeps it once it is
+    /// on disk, so a failed write leaves the store as it was.
+    fn update(&self, change: impl FnOnce(&mut Document)) -> Result<()> {
+        let mut document = self.document.lock().unwrap();
+
+        let mut updated = document.clone();
+        change(&mut updated);
+        self.write(&updated)?;
+        *document = updated;
+
+        Ok(())
+    }
+
+    fn write(&self, document: &Document) -> Result<()> {
+        let mut tmp_path = self
+            .path
+            .clone()
+            .into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&serde_json::to_vec_pretty(document)?)?;
+        file.sync_all()?;
+
+        fs::rename(&tmp_path, &self.path)?;
+
+        // Persist the rename itself.
+        #[cfg(unix)]
+        if let Some(dir) = self.path.parent() {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+
+            File::open(dir)?.sync_all()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Store for JsonStore {
+    fn last_run(&self, task: &str) -> Result<Option<RunRecord>> {
+        let document = self.document.lock().unwrap();
+
+        Ok(document
+            .tasks
+            .get(task)
+            .and_then(|task| {
+                // Reversed so a rerun due at the same time wins.
+                task.runs
+                    .iter()
+                    .rev()
+                    .filter(|run| run.ran())
+                    .max_by_key(|run| run.scheduled_at)
+            })
+            .cloned())
+    }
+
+    fn record_run(&self, task: &str, run: &RunRecord, values: &Values) -> Result<()> {
+        self.update(|document| {
+            let task = document
+                .tasks
+                .entry(String::from(task))
+                .or_default();
+            task.state
+                .extend(values.clone());
+
+            // Overlapping runs finish out of order, so the history is kept
+            // in the order they were due.
+            let runs = &mut task.runs;
+            let position = runs.partition_point(|other| other.scheduled_at > run.scheduled_at);
+            runs.insert(position, run.clone());
+
+            if runs.len() > HISTORY_LIMIT {
+                let last_ran = runs
+                    .iter()
+                    .position(RunRecord::ran)
+                    .filter(|position| *position >= HISTORY_LIMIT)
+                    .and_then(|position| runs.remove(position));
+
+                runs.truncate(HISTORY_LIMIT);
+                runs.extend(last_ran);
+            }
+        })
+    }
+
+    fn backfill_progress(&self, id: &str) -> Result<Option<DateTime<Utc>>> {
+        let document = self.document.lock().unwrap();
+
+        Ok(document
+            .backfills
+            .get(id)
+            .copied())
+    }
+
+    fn record_backfill_progress(&self, id: &str, done_until: DateTime<Utc>) -> Result<()> {
+        self.update(|document| {
+            document
+                .backfills
+                .insert(String::from(id), done_until);
+        })
+    }
+
+    fn state(&self, task: &str) -> Result<Values> {
+        let document = self.document.lock().unwrap();
+
+        Ok(document
+            .tasks
+            .get(task)
+            .map(|task| task.state.clone())
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::Outcome;
+    use crate::state::fixtures::run;
+
+    #[test]
+    fn test_json_store_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+
+        let store = JsonStore::open(&path).unwrap();
+        assert_eq!(
+            None,
+            store
+                .last_run("load_data")
+                .unwrap()
+        );
+
+        store
+            .record_run("load_data", &run(0, Outcome::Success), &Values::new())
+            .unwrap();
+        store
+            .record_run(
+                "load_data",
+                &run(5, Outcome::Failure(String::from("timeout"))),
+                &Values::new(),
+            )
+            .unwrap();
+        drop(store);
+
+        let store = JsonStore::open(&path).unwrap();
+        assert_eq!(
+            Some(run(5, Outcome::Failure(String::from("timeout")))),
+            store
+                .last_run("load_data")
+                .unwrap()
+        );
+        assert_eq!(
+            vec![
+                run(5, Outcome::Failure(String::from("timeout"))),
+                run(0, Outcome::Success)
+            ],
+            Vec::from(
+                store
+                    .document
+                    .lock()
+                    .unwrap()
+                    .tasks["load_data"]
+                    .runs
+                    .clone()
+            )
+        );
+        assert_eq!(
+            None,
+            store
+                .last_run("push_data")
+                .unwrap()
+        );
+        assert!(!dir
+            .path()
+            .join("state.json.tmp")
+            .exists());
+    }
+
+    #[test]
+    fn test_json_store_keeps_backfill_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+
+        let store = JsonStore::open(&path).unwrap();
+        assert_eq!(
+            None,
+            store
+                .backfill_progress("load_data")
+                .unwrap()
+        );
+        store
+            .record_backfill_progress("load_data", run(0, Outcome::Success).scheduled_at)
+            .unwrap();
+        store
+            .record_backfill_progress("load_data", run(5, Outcome::Success).scheduled_at)
+            .unwrap();
+        drop(store);
+
+        let store = JsonStore::open(&path).unwrap();
+        assert_eq!(
+            Some(run(5, Outcome::Success).scheduled_at),
+            store
+                .backfill_progress("load_data")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_json_store_merges_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+
+        let store = JsonStore::open(&path).unwrap();
+        assert_eq!(
+            Values::new(),
+            store
+                .state("load_data")
+                .unwrap()
+        );
+        store
+            .record_run(
+                "load_data",
+                &run(0, Outcome::Success),
+                &Values::from([
+                    (String::from("cursor"), serde_json::json!("a")),
+                    (String::from("max_id"), serde_json::json!(1)),
+                ]),
+            )
+            .unwrap();
+        store
+            .record_run(
+                "load_data",
+                &run(0, Outcome::Success),
+                &Values::from([(String::from("cursor"), serde_json::json!("b"))]),
+            )
+            .unwrap();
+        drop(store);
+
+        let store = JsonStore::open(&path).unwrap();
+        assert_eq!(
+            Values::from([
+                (String::from("cursor"), serde_json::json!("b")),
+                (String::from("max_id"), serde_json::json!(1)),
+            ]),
+            store
+                .state("load_data")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_json_store_leaves_skipped_runs_out_of_last_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        let store = JsonStore::open(&path).unwrap();
+
+        store
+            .record_run("load_data", &run(0, Outcome::Success), &Values::new())
+            .unwrap();
+        for _ in 0..HISTORY_LIMIT {
+            store
+                .record_run(
+                    "load_data",
+                    &run(
+                        1,
+                        Outcome::Skipped(String::from("previous run still in flight")),
+                    ),
+                    &Values::new(),
+                )
+                .unwrap();
+        }
+        store
+            .record_run("load_data", &run(2, Outcome::Replaced), &Values::new())
+            .unwrap();
+        drop(store);
+
+        let store = JsonStore::open(&path).unwrap();
+
+        assert_eq!(
+            Some(run(0, Outcome::Success)),
+            store
+                .last_run("load_data")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_json_store_orders_runs_by_scheduled_at() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        let store = JsonStore::open(&path).unwrap();
+
+        store
+            .record_run("load_data", &run(5, Outcome::Success), &Values::new())
+            .unwrap();
+        for _ in 0..HISTORY_LIMIT {
+            store
+                .record_run(
+                    "load_data",
+                    &run(0, Outcome::Failure(String::from("timeout"))),
+                    &Values::new(),
+                )
+                .unwrap();
+        }
+        drop(store);
+
+        let store = JsonStore::open(&path).unwrap();
+
+        assert_eq!(
+            Some(run(5, Outcome::Success)),
+            store
+                .last_run("load_data")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_json_store_keeps_limited_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = JsonStore::open(dir.path().join("state.json")).unwrap();
+
+        for _ in 0..HISTORY_LIMIT + 5 {
+            store
+                .record_run("load_data", &run(0, Outcome::Success), &Values::new())
+                .unwrap();
+        }
+
+        let document: Document =
+            serde_json::from_slice(&fs::read(dir.path().join("state.json")).unwrap()).unwrap();
+
+        assert_eq!(
+            HISTORY_LIMIT,
+            document.tasks["load_data"]
+                .runs
+                .len()
+        );
+    }
+
+    #[test]
+    fn test_json_store_rejects_corrupted_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        fs::write(&path, "{\"tasks\": ").unwrap();
+
+        assert!(matches!(
+            JsonStore::open(&path),
+            Err(super::super::Error::Json(_))
+        ));
+    }
+}
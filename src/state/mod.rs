@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod fixtures;
+pub mod json;
+pub mod sqlite;
+
+use crate::config;
+use crate::executor::{Attempt, Outcome, Page};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+/// How many runs per task the stores keep.
+pub const HISTORY_LIMIT: usize = 100;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "state store io error: {err}"),
+            Error::Json(err) => write!(f, "invalid state file: {err}"),
+            Error::Sqlite(err) => write!(f, "state database error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::Sqlite(err)
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Values a task extracted from its responses, by name.
+pub type Values = config::extract::Values;
+
+/// A finished run of a task.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    pub scheduled_at: DateTime<Utc>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub outcome: Outcome,
+    #[serde(default)]
+    pub attempts: Vec<Attempt>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pages: Vec<Page>,
+}
+
+impl RunRecord {
+    /// Whether the run was executed rather than skipped or replaced.
+    pub fn ran(&self) -> bool {
+        !matches!(self.outcome, Outcome::Skipped(_) | Outcome::Replaced)
+    }
+}
+
+/// Persists what happened to every task so it survives restarts.
+pub trait Store: Send + Sync {
+    /// Returns the run of `task` with the latest `scheduled_at` that ran,
+    /// leaving out skipped and replaced ones.
+    fn last_run(&self, task: &str) -> Result<Option<RunRecord>>;
+
+    /// Adds a run to the history of `task` and merges the `values` it
+    /// extracted into the task's state, both in one write. The history keeps
+    /// the `HISTORY_LIMIT` runs scheduled latest, and the latest one that ran
+    /// even when it is older, however the runs were recorded.
+    fn record_run(&self, task: &str, run: &RunRecord, values: &Values) -> Result<()>;
+
+    /// Returns the latest run of the backfill `id` up to which every run
+    /// succeeded.
+    fn backfill_progress(&self, id: &str) -> Result<Option<DateTime<Utc>>>;
+
+    fn record_backfill_progress(&self, id: &str, done_until: DateTime<Utc>) -> Result<()>;
+
+    /// Returns the values `task` extracted so far.
+    fn state(&self, task: &str) -> Result<Values>;
+}
+
+/// Runs `call` on the blocking pool. The stores write through fsyncs and
+/// synchronous SQLite, which must not hold up the async workers.
+pub async fn blocking<T, F>(store: &Arc<dyn Store>, call: F) -> Result<T>
+where
+    F: FnOnce(&dyn Store) -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let store = store.clone();
+
+    tokio::task::spawn_blocking(move || call(store.as_ref()))
+        .await
+        .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+}
+
+pub fn open(config: &config::store::Store) -> Result<Arc<dyn Store>> {
+    match config {
+        config::store::Store::Json { path } => Ok(Arc::new(json::JsonStore::open(path)?)),
+        config::store::Store::Sqlite { path } => Ok(Arc::new(sqlite::SqliteStore::open(path)?)),
+    }
+}
@@ -0,0 +1,383 @@
+use super::{Result, RunRecord, Store, Values, HISTORY_LIMIT};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Filters runs that ran rather than being skipped or replaced.
+const RAN: &str = "json_extract(record, '$.outcome.status') NOT IN ('skipped', 'replaced')";
+
+/// Latest due first, as overlapping runs finish out of order. RFC3339 dates
+/// in UTC sort as text.
+const LATEST: &str = "ORDER BY scheduled_at DESC, id DESC";
+
+/// Keeps runs in a SQLite database.
+///
+/// The database runs in WAL mode with full syncs and every change is a single
+/// transaction, so a crash never leaves a partially recorded run behind.
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let connection = Connection::open(path)?;
+
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.pragma_update(None, "synchronous", "FULL")?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task TEXT NOT NULL,
+                scheduled_at TEXT NOT NULL,
+                record TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS runs_task_id ON runs (task, id);
+            CREATE INDEX IF NOT EXISTS runs_task_scheduled_at ON runs (task, scheduled_at);
+            CREATE TABLE IF NOT EXISTS backfills (
+                id TEXT PRIMARY KEY,
+                done_until TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS state (
+                task TEXT PRIMARY KEY,
+                state TEXT NOT NULL
+            );",
+        )?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl Store for SqliteStore {
+    fn last_run(&self, task: &str) -> Result<Option<RunRecord>> {
+        let connection = self
+            .connection
+            .lock()
+            .unwrap();
+
+        let record: Option<String> = connection
+            .query_row(
+                &format!("SELECT record FROM runs WHERE task = ?1 AND {RAN} {LATEST} LIMIT 1"),
+                params![task],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match record {
+            Some(record) => Ok(Some(serde_json::from_str(&record)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn record_run(&self, task: &str, run: &RunRecord, values: &Values) -> Result<()> {
+        let mut connection = self
+            .connection
+            .lock()
+            .unwrap();
+        let record = serde_json::to_string(run)?;
+
+        let transaction = connection.transaction()?;
+
+        transaction.execute(
+            "INSERT INTO runs (task, scheduled_at, record) VALUES (?1, ?2, ?3)",
+            params![task, run.scheduled_at.to_rfc3339(), record],
+        )?;
+
+        transaction.execute(
+            &format!(
+                "DELETE FROM runs WHERE task = ?1
+                AND id NOT IN (SELECT id FROM runs WHERE task = ?1 {LATEST} LIMIT ?2)
+                AND id IS NOT (SELECT id FROM runs WHERE task = ?1 AND {RAN} {LATEST} LIMIT 1)"
+            ),
+            params![task, HISTORY_LIMIT as i64],
+        )?;
+
+        if !values.is_empty() {
+            let mut state = read_state(&transaction, task)?;
+            state.extend(values.clone());
+
+            transaction.execute(
+                "INSERT INTO state (task, state) VALUES (?1, ?2)
+                ON CONFLICT (task) DO UPDATE SET state = excluded.state",
+                params![task, serde_json::to_string(&state)?],
+            )?;
+        }
+
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    fn backfill_progress(&self, id: &str) -> Result<Option<DateTime<Utc>>> {
+        let connection = self
+            .connection
+            .lock()
+            .unwrap();
+
+        Ok(connection
+            .query_row(
+                "SELECT done_until FROM backfills WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn record_backfill_progress(&self, id: &str, done_until: DateTime<Utc>) -> Result<()> {
+        let connection = self
+            .connection
+            .lock()
+            .unwrap();
+
+        connection.execute(
+            "INSERT INTO backfills (id, done_until) VALUES (?1, ?2)
+            ON CONFLICT (id) DO UPDATE SET done_until = excluded.done_until",
+            params![id, done_until],
+        )?;
+
+        Ok(())
+    }
+
+    fn state(&self, task: &str) -> Result<Values> {
+        let connection = self
+            .connection
+            .lock()
+            .unwrap();
+
+        read_state(&connection, task)
+    }
+}
+
+fn read_state(connection: &Connection, task: &str) -> Result<Values> {
+    let state: Option<String> = connection
+        .query_row(
+            "SELECT state FROM state WHERE task = ?1",
+            params![task],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match state {
+        Some(state) => Ok(serde_json::from_str(&state)?),
+        None => Ok(Values::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::Outcome;
+    use crate::state::fixtures::run;
+
+    #[test]
+    fn test_sqlite_store_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.db");
+
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(
+            None,
+            store
+                .last_run("load_data")
+                .unwrap()
+        );
+
+        store
+            .record_run("load_data", &run(0, Outcome::Success), &Values::new())
+            .unwrap();
+        store
+            .record_run(
+                "load_data",
+                &run(5, Outcome::Failure(String::from("timeout"))),
+                &Values::new(),
+            )
+            .unwrap();
+        store
+            .record_run("push_data", &run(7, Outcome::Success), &Values::new())
+            .unwrap();
+        drop(store);
+
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(
+            Some(run(5, Outcome::Failure(String::from("timeout")))),
+            store
+                .last_run("load_data")
+                .unwrap()
+        );
+        assert_eq!(
+            Some(run(7, Outcome::Success)),
+            store
+                .last_run("push_data")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sqlite_store_keeps_backfill_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.db");
+
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(
+            None,
+            store
+                .backfill_progress("load_data")
+                .unwrap()
+        );
+        store
+            .record_backfill_progress("load_data", run(0, Outcome::Success).scheduled_at)
+            .unwrap();
+        store
+            .record_backfill_progress("load_data", run(5, Outcome::Success).scheduled_at)
+            .unwrap();
+        drop(store);
+
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(
+            Some(run(5, Outcome::Success).scheduled_at),
+            store
+                .backfill_progress("load_data")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sqlite_store_merges_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.db");
+
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(
+            Values::new(),
+            store
+                .state("load_data")
+                .unwrap()
+        );
+        store
+            .record_run(
+                "load_data",
+                &run(0, Outcome::Success),
+                &Values::from([
+                    (String::from("cursor"), serde_json::json!("a")),
+                    (String::from("max_id"), serde_json::json!(1)),
+                ]),
+            )
+            .unwrap();
+        store
+            .record_run(
+                "load_data",
+                &run(0, Outcome::Success),
+                &Values::from([(String::from("cursor"), serde_json::json!("b"))]),
+            )
+            .unwrap();
+        drop(store);
+
+        let store = SqliteStore::open(&path).unwrap();
+        assert_eq!(
+            Values::from([
+                (String::from("cursor"), serde_json::json!("b")),
+                (String::from("max_id"), serde_json::json!(1)),
+            ]),
+            store
+                .state("load_data")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sqlite_store_leaves_skipped_runs_out_of_last_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = SqliteStore::open(&path).unwrap();
+
+        store
+            .record_run("load_data", &run(0, Outcome::Success), &Values::new())
+            .unwrap();
+        for _ in 0..HISTORY_LIMIT {
+            store
+                .record_run(
+                    "load_data",
+                    &run(
+                        1,
+                        Outcome::Skipped(String::from("previous run still in flight")),
+                    ),
+                    &Values::new(),
+                )
+                .unwrap();
+        }
+        store
+            .record_run("load_data", &run(2, Outcome::Replaced), &Values::new())
+            .unwrap();
+        drop(store);
+
+        let store = SqliteStore::open(&path).unwrap();
+
+        assert_eq!(
+            Some(run(0, Outcome::Success)),
+            store
+                .last_run("load_data")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sqlite_store_orders_runs_by_scheduled_at() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.db");
+        let store = SqliteStore::open(&path).unwrap();
+
+        store
+            .record_run("load_data", &run(5, Outcome::Success), &Values::new())
+            .unwrap();
+        for _ in 0..HISTORY_LIMIT {
+            store
+                .record_run(
+                    "load_data",
+                    &run(0, Outcome::Failure(String::from("timeout"))),
+                    &Values::new(),
+                )
+                .unwrap();
+        }
+        drop(store);
+
+        let store = SqliteStore::open(&path).unwrap();
+
+        assert_eq!(
+            Some(run(5, Outcome::Success)),
+            store
+                .last_run("load_data")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sqlite_store_keeps_limited_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SqliteStore::open(dir.path().join("state.db")).unwrap();
+
+        for minute in 0..HISTORY_LIMIT as u32 + 5 {
+            store
+                .record_run(
+                    "load_data",
+                    &run(minute % 60, Outcome::Success),
+                    &Values::new(),
+                )
+                .unwrap();
+        }
+
+        let count: i64 = store
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*) FROM runs WHERE task = 'load_data'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+
+        assert_eq!(HISTORY_LIMIT as i64, count);
+    }
+}
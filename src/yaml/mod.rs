@@ -1,12 +1,15 @@
 use std::collections::BTreeMap;
 use std::fmt;
 
+use serde_yml::value::TaggedValue;
 use serde_yml::{self, Mapping, Value};
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
     EnvVarNotFound { env_name: String },
     InvalidEnvSyntax,
+    InvalidTagValue { tag: String, expected: &'static str },
+    FileNotReadable { path: String, reason: String },
 }
 
 impl fmt::Display for Error {
@@ -14,6 +17,10 @@ impl fmt::Display for Error {
         match self {
             Error::EnvVarNotFound { env_name } => write!(f, "env {env_name} not found"),
             Error::InvalidEnvSyntax => write!(f, "invalid env!() syntax"),
+            Error::InvalidTagValue { tag, expected } => write!(f, "{tag} takes {expected}"),
+            Error::FileNotReadable { path, reason } => {
+                write!(f, "failed to read {path}: {reason}")
+            }
         }
     }
 }
@@ -41,10 +48,92 @@ impl EnvReplacer {
             serde_yml::Value::String(s) => self.replace_string(s),
             serde_yml::Value::Sequence(vec) => self.replace_sequence(vec),
             serde_yml::Value::Mapping(map) => self.replace_mapping(map),
-            serde_yml::Value::Tagged(_) => Ok(value),
+            serde_yml::Value::Tagged(tagged) => self.replace_tagged(*tagged),
         }
     }
 
+    /// Resolves `!env NAME` and `!file PATH` to their content and rewrites
+    /// `!source` and `!template` into the mappings values are parsed from.
+    /// Other tags are kept.
+    fn replace_tagged(&self, tagged: TaggedValue) -> Result<Value> {
+        let TaggedValue { tag, value } = tagged;
+        let value = self.replace_value(value)?;
+
+        let invalid = |expected| Error::InvalidTagValue {
+            tag: tag.to_string(),
+            expected,
+        };
+
+        if tag == "env" {
+            let env_name = value
+                .as_str()
+                .ok_or_else(|| invalid("a variable name"))?;
+
+            return self
+                .envs
+                .get(env_name)
+                .map(|env| Value::String(env.clone()))
+                .ok_or(Error::EnvVarNotFound {
+                    env_name: String::from(env_name),
+                });
+        }
+
+        if tag == "file" {
+            let path = value
+                .as_str()
+                .ok_or_else(|| invalid("a path"))?;
+
+            let content = std::fs::read_to_string(path).map_err(|err| Error::FileNotReadable {
+                path: String::from(path),
+                reason: err.to_string(),
+            })?;
+
+            // Files written by editors and `echo` end in a newline that is
+            // not part of the secret.
+            let content = content
+                .strip_suffix('\n')
+                .map(|content| {
+                    content
+                        .strip_suffix('\r')
+                        .unwrap_or(content)
+                })
+                .unwrap_or(&content);
+
+            return Ok(Value::String(String::from(content)));
+        }
+
+        let typed = |entry_type: &str, key: &str, value: Value| {
+            Mapping::from_iter([
+                (Value::from("type"), Value::from(entry_type)),
+                (Value::from(key), value),
+            ])
+        };
+
+        if tag == "source" {
+            // `!source {source: execute_time, format: unix}` keeps its options.
+            return match value {
+                Value::String(_) => Ok(Value::Mapping(typed("source", "source", value))),
+                Value::Mapping(options) => {
+                    let mut entry =
+                        Mapping::from_iter([(Value::from("type"), Value::from("source"))]);
+                    entry.extend(options);
+
+                    Ok(Value::Mapping(entry))
+                }
+                _ => Err(invalid("a source or a mapping with `source`")),
+            };
+        }
+
+        if tag == "template" {
+            return match value {
+                Value::String(_) => Ok(Value::Mapping(typed("template", "value", value))),
+                _ => Err(invalid("a string")),
+            };
+        }
+
+        Ok(Value::Tagged(Box::new(TaggedValue { tag, value })))
+    }
+
     fn replace_string(&self, s: String) -> Result<Value> {
         let res = Self::find_env(s.as_str())?;
 
@@ -61,7 +150,7 @@ impl EnvReplacer {
 
                 self.replace_string(value)
             }
-            None => return Ok(Value::String(s)),
+            None => Ok(Value::String(s)),
         }
     }
 
@@ -160,6 +249,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_env_replacer_tags() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret = dir.path().join("secret.txt");
+        std::fs::write(&secret, "s3cr3t\n").unwrap();
+
+        let replacer = EnvReplacer {
+            envs: BTreeMap::from_iter([(String::from("TOKEN"), String::from("example_token"))]),
+        };
+
+        env_replacer_success(&replacer, "test: !env TOKEN", "test: example_token\n");
+        env_replacer_success(
+            &replacer,
+            &format!("test: !file {}", secret.display()),
+            "test: s3cr3t\n",
+        );
+        env_replacer_success(
+            &replacer,
+            "test: !source execute_time",
+            "test:\n  type: source\n  source: execute_time\n",
+        );
+        env_replacer_success(
+            &replacer,
+            "test: !source {source: last_execute_time, format: unix}",
+            "test:\n  type: source\n  source: last_execute_time\n  format: unix\n",
+        );
+        env_replacer_success(
+            &replacer,
+            "test: !template \"Bearer env!(TOKEN) {{ state.token }}\"",
+            "test:\n  type: template\n  value: Bearer example_token {{ state.token }}\n",
+        );
+        env_replacer_success(&replacer, "test: !custom 1", "test: !custom 1\n");
+        env_replacer_failure(
+            &replacer,
+            "test: !env RANDOM_ENV",
+            super::Error::EnvVarNotFound {
+                env_name: String::from("RANDOM_ENV"),
+            },
+        );
+        env_replacer_failure(
+            &replacer,
+            "test: !env [TOKEN]",
+            super::Error::InvalidTagValue {
+                tag: String::from("!env"),
+                expected: "a variable name",
+            },
+        );
+        env_replacer_failure(
+            &replacer,
+            "test: !template {value: x}",
+            super::Error::InvalidTagValue {
+                tag: String::from("!template"),
+                expected: "a string",
+            },
+        );
+
+        let missing = dir.path().join("missing.txt");
+        let err = replacer
+            .replace_value(
+                serde_yml::from_str(&format!("test: !file {}", missing.display())).unwrap(),
+            )
+            .err()
+            .unwrap();
+
+        assert!(err
+            .to_string()
+            .starts_with(&format!("failed to read {}: ", missing.display())));
+    }
+
     fn env_replacer_success(replacer: &EnvReplacer, input: &str, expected_output: &str) {
         let value = serde_yml::from_str(input).unwrap();
 